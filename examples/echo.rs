@@ -1,5 +1,4 @@
 use fibra::*;
-use bytes::BytesMut;
 
 #[tokio::main]
 async fn main() -> FibraResult<()> {
@@ -8,6 +7,9 @@ async fn main() -> FibraResult<()> {
     app.mount(addon::ReqID::new());
     app.mount(addon::Logger::new());
 
+    // limit body's size, larger bodies are rejected with 413
+    app.max_body_size(1024); // 1KB
+
     // <- http -v localip.cc:3000 name=echo
     // -> {"name":"echo"}
     app.post("/", echo)?;
@@ -18,21 +20,7 @@ async fn main() -> FibraResult<()> {
 
 async fn echo(mut ctx: Context) -> FibraResult<Response> {
     let kind = ctx.header(header::CONTENT_TYPE).cloned().unwrap_or(mime::APPLICATION_OCTET_STREAM.into_header_value());
-    let mut body = BytesMut::new();
-
-    // read chunk by chunk and limit body's size
-    let limits = 1024; // 1KB
-    let mut length = 0;
-
-    while let Some(chunk) = ctx.read_frame().await {
-        length += chunk.len();
-
-        if length > limits {
-            return Ok(Status::PAYLOAD_TOO_LARGE.into());
-        }
-
-        body.extend(chunk);
-    }
+    let body = ctx.try_read_all().await?;
 
-    Ok(Response::new().header(header::CONTENT_TYPE, kind).body(body.freeze()))
+    Ok(Response::new().header(header::CONTENT_TYPE, kind).body(body))
 }
\ No newline at end of file
@@ -8,7 +8,7 @@ async fn main() -> FibraResult<()> {
     app.get("/function", function)?;
 
     // $ http -v localip.cc:3000/closure
-    app.get("/closure", |_| async { Ok("Closure".into()) })?;
+    app.get("/closure", |_: Context| async { "Closure" })?;
 
     // $ http -v localip.cc:3000/tuple-smt
     app.get("/tuple-smt", (Status::OK, mime::APPLICATION_JSON, "{\"type\":\"Tuple SMT\"}"))?;
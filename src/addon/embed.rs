@@ -0,0 +1,354 @@
+//! Embedded Assets
+use super::statics::*;
+use crate::route::*;
+use crate::types::*;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Embed the assets generated by `Embed::generate` in build.rs and create the handler
+///
+/// # Examples
+///
+/// ```ignore
+/// // build.rs
+/// fn main() {
+///     fibra::addon::Embed::generate("public", "assets.rs").expect("embed public");
+/// }
+///
+/// // main.rs
+/// #[macro_use] extern crate fibra;
+///
+/// let mut app = fibra::Fibra::new();
+/// app.mount(embed!("assets.rs").fallback("index.html"));
+/// ```
+#[macro_export]
+macro_rules! embed {
+    ($name:literal) => {{
+        use $crate::addon::Asset;
+        static ASSETS: &[Asset] = include!(concat!(env!("OUT_DIR"), "/", $name));
+        $crate::addon::Embed::new(ASSETS)
+    }};
+}
+
+/// A file compiled into the binary, the validators and variants are computed at build time
+#[derive(Debug, Clone, Copy)]
+pub struct Asset {
+    /// The path relative to the embedded directory, separated by slashes
+    pub path: &'static str,
+
+    /// The content
+    pub data: &'static [u8],
+
+    /// The MIME type
+    pub mime: &'static str,
+
+    /// The quoted etag derived from the content
+    pub etag: &'static str,
+
+    /// The last modified time in unix seconds, zero if unknown
+    pub modified: u64,
+
+    /// The precompressed variants with their content encodings
+    pub encoded: &'static [(&'static str, &'static [u8])],
+}
+
+/// Serve assets compiled into the binary, the lookup works like `Static` without touching the
+/// file system, conditional and range requests are honored
+///
+/// # Examples
+///
+/// ```
+/// use fibra::*;
+/// use fibra::addon::Asset;
+///
+/// static ASSETS: &[Asset] = &[
+///     Asset { path: "index.html", data: b"<h1>Home</h1>", mime: "text/html", etag: "\"1\"", modified: 0, encoded: &[] },
+///     Asset { path: "docs/index.html", data: b"<h1>Docs</h1>", mime: "text/html", etag: "\"2\"", modified: 0, encoded: &[] },
+///     Asset { path: "app.js", data: b"console.log(1)", mime: "text/javascript", etag: "\"3\"", modified: 0, encoded: &[("gzip", b"gzipped")] },
+/// ];
+///
+/// #[tokio::main]
+/// async fn main() -> FibraResult<()> {
+///     let get = |req: Request| {
+///         let mut app = Fibra::new();
+///         app.mount(addon::Embed::new(ASSETS).fallback("index.html"));
+///         Context::from((app, req))
+///     };
+///
+///     // index file
+///     let mut res = get(Request::new().uri("http://localhost/")).next().await?;
+///     assert_eq!(res.header_ref(header::ETAG).map(|v| v.as_bytes()), Some("\"1\"".as_bytes()));
+///     assert_eq!(res.body_all().await.unwrap_or_default(), "<h1>Home</h1>");
+///
+///     // conditional get
+///     let res = get(Request::new().uri("http://localhost/").header(header::IF_NONE_MATCH, "\"1\"")).next().await?;
+///     assert_eq!(res.status_ref(), &Status::NOT_MODIFIED);
+///
+///     // precompressed variant
+///     let mut res = get(Request::new().uri("http://localhost/app.js").header(header::ACCEPT_ENCODING, "gzip, deflate")).next().await?;
+///     assert_eq!(res.header_ref(header::CONTENT_ENCODING).map(|v| v.as_bytes()), Some("gzip".as_bytes()));
+///     assert_eq!(res.header_ref(header::ETAG).map(|v| v.as_bytes()), Some("\"3-gzip\"".as_bytes()));
+///     assert_eq!(res.body_all().await.unwrap_or_default(), "gzipped");
+///
+///     // range request
+///     let mut res = get(Request::new().uri("http://localhost/app.js").header(header::RANGE, "bytes=0-6")).next().await?;
+///     assert_eq!(res.status_ref(), &Status::PARTIAL_CONTENT);
+///     assert_eq!(res.body_all().await.unwrap_or_default(), "console");
+///
+///     // directory without trailing slash
+///     let res = get(Request::new().uri("http://localhost/docs")).next().await?;
+///     assert_eq!(res.header_ref(header::LOCATION).map(|v| v.as_bytes()), Some("/docs/".as_bytes()));
+///
+///     // single page app fallback
+///     let mut res = get(Request::new().uri("http://localhost/users/42")).next().await?;
+///     assert_eq!(res.body_all().await.unwrap_or_default(), "<h1>Home</h1>");
+///
+///     Ok(())
+/// }
+/// ```
+pub struct Embed {
+    assets: HashMap<&'static str, &'static Asset>,
+    index: Option<String>,
+    fallback: Option<String>,
+}
+
+impl Embed {
+    /// Create a new object, usually by the `embed!` macro, index.html is served for directories
+    /// by default
+    #[inline]
+    pub fn new(assets: &'static [Asset]) -> Self {
+        Self {
+            assets: assets.iter().map(|asset| (asset.path, asset)).collect(),
+            index: Some("index.html".to_string()),
+            fallback: None,
+        }
+    }
+
+    /// Set the index file of directories, None to disable it
+    #[inline]
+    pub fn index(mut self, index: Option<&str>) -> Self {
+        self.index = index.map(|v| v.to_string());
+        self
+    }
+
+    /// Serve an asset instead when nothing matches, e.g. the index.html of a single page app
+    #[inline]
+    pub fn fallback(mut self, path: impl Into<String>) -> Self {
+        self.fallback = Some(path.into());
+        self
+    }
+
+    /// Find an asset by its relative path
+    #[inline]
+    pub fn get(&self, path: &str) -> Option<&'static Asset> {
+        self.assets.get(path).copied()
+    }
+
+    /// Generate the assets of a directory into OUT_DIR, call it in build.rs and include the file
+    /// by the `embed!` macro, dotfiles are skipped, compressible files are gzipped if smaller,
+    /// existing .br and .gz siblings are used as variants instead of standalone assets
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// fn main() -> FibraResult<()> {
+    ///     let root = std::env::temp_dir().join("fibra-embed");
+    ///     let out = root.join("out");
+    ///
+    ///     std::fs::create_dir_all(root.join("public").join("css"))?;
+    ///     std::fs::create_dir_all(&out)?;
+    ///     std::fs::write(root.join("public").join("index.html"), "<h1>Home</h1>".repeat(100))?;
+    ///     std::fs::write(root.join("public").join("css").join("app.css"), "body {}")?;
+    ///     std::fs::write(root.join("public").join("css").join("app.css.br"), "br")?;
+    ///     std::fs::write(root.join("public").join(".env"), "secret")?;
+    ///
+    ///     std::env::set_var("OUT_DIR", &out);
+    ///     addon::Embed::generate(root.join("public"), "assets.rs")?;
+    ///
+    ///     let code = std::fs::read_to_string(out.join("assets.rs"))?;
+    ///
+    ///     assert_eq!(code.contains("path: \"index.html\""), true);
+    ///     assert_eq!(code.contains("mime: \"text/css\""), true);
+    ///     assert_eq!(code.contains("(\"gzip\", include_bytes!("), true);
+    ///     assert_eq!(code.contains("(\"br\", include_bytes!("), true);
+    ///     assert_eq!(code.contains("path: \"css/app.css.br\""), false);
+    ///     assert_eq!(code.contains(".env"), false);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn generate(dir: impl AsRef<Path>, name: &str) -> FibraResult<()> {
+        use sha1::Digest;
+
+        let dir = std::fs::canonicalize(dir)?;
+        let out = PathBuf::from(std::env::var_os("OUT_DIR").ok_or(FibraError::PanicError("OUT_DIR is not set, call it in build.rs".into()))?);
+        let mut files = vec![];
+        let mut code = String::from("&[\n");
+
+        collect(&dir, &mut files)?;
+
+        for (idx, file) in files.iter().enumerate() {
+            // precompressed siblings are embedded as variants of their origins only
+            if ENCODINGS.iter().any(|(_, ext)| file.extension().is_some_and(|v| v == *ext) && files.contains(&file.with_extension(""))) {
+                continue;
+            }
+
+            let path = match file.strip_prefix(&dir).ok().and_then(|v| v.iter().map(|v| v.to_str()).collect::<Option<Vec<_>>>()) {
+                Some(parts) => parts.join("/"),
+                None => continue,
+            };
+
+            let data = std::fs::read(file)?;
+            let mime = mime_guess::from_path(file).first_or_octet_stream();
+            let etag = format!("\"{}\"", sha1::Sha1::digest(&data)[..8].iter().map(|v| format!("{:02x}", v)).collect::<String>());
+            let modified = std::fs::metadata(file)?.modified().ok().and_then(|v| v.duration_since(UNIX_EPOCH).ok()).map(|v| v.as_secs()).unwrap_or(0);
+            let mut encoded = vec![];
+
+            for (encoding, ext) in ENCODINGS {
+                let mut sibling = file.as_os_str().to_owned();
+                sibling.push(".");
+                sibling.push(ext);
+
+                let sibling = PathBuf::from(sibling);
+
+                if sibling.is_file() {
+                    encoded.push((encoding, sibling));
+                }
+            }
+
+            if compressible(&mime) && !encoded.iter().any(|(encoding, _)| *encoding == "gzip") {
+                let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::best());
+                encoder.write_all(&data)?;
+                let gzip = encoder.finish()?;
+
+                if gzip.len() < data.len() {
+                    let target = out.join(format!("{}-{}.gz", name, idx));
+                    std::fs::write(&target, gzip)?;
+                    encoded.push(("gzip", target));
+                }
+            }
+
+            let encoded = encoded.iter().map(|(encoding, path)| format!("({:?}, include_bytes!({:?}))", encoding, path)).collect::<Vec<_>>().join(", ");
+
+            code.push_str(&format!(
+                "    Asset {{ path: {:?}, data: include_bytes!({:?}), mime: {:?}, etag: {:?}, modified: {}, encoded: &[{}] }},\n",
+                path, file, mime.essence_str(), etag, modified, encoded
+            ));
+        }
+
+        code.push(']');
+        std::fs::write(out.join(name), code)?;
+
+        println!("cargo:rerun-if-changed={}", dir.display());
+
+        Ok(())
+    }
+
+    /// Serve an asset or its precompressed variant
+    fn serve(&self, asset: &'static Asset, req: &Request) -> Response {
+        let mime = asset.mime.parse().unwrap_or(mime::APPLICATION_OCTET_STREAM);
+        let modified = Some(UNIX_EPOCH + Duration::from_secs(asset.modified)).filter(|_| asset.modified > 0);
+
+        if asset.encoded.is_empty() {
+            return Entity::memory(Bytes::from_static(asset.data), mime, asset.etag.to_string(), modified).respond(Response::new(), Some(req));
+        }
+
+        let res = Response::new().header(header::VARY, "accept-encoding");
+
+        for (encoding, _) in accepted(req) {
+            if let Some((_, data)) = asset.encoded.iter().find(|(name, _)| *name == encoding) {
+                return Entity::memory(Bytes::from_static(data), mime, asset.etag.to_string(), modified).encoding(encoding).respond(res, Some(req));
+            }
+        }
+
+        Entity::memory(Bytes::from_static(asset.data), mime, asset.etag.to_string(), modified).respond(res, Some(req))
+    }
+}
+
+#[async_trait]
+impl Handler for Embed {
+    async fn handle(&self, ctx: Context) -> FibraResult<Response> {
+        if !matches!(*ctx.method(), Method::GET | Method::HEAD) {
+            return ctx.next().await;
+        }
+
+        let path = match normalize(&rest(&ctx)) {
+            Some(path) => path,
+            None => return ctx.next().await,
+        };
+
+        if let Some(asset) = self.get(&path) {
+            return Ok(self.serve(asset, ctx.req()));
+        }
+
+        if let Some(index) = &self.index {
+            let index = match path.is_empty() {
+                true => index.clone(),
+                false => format!("{}/{}", path, index),
+            };
+
+            if let Some(asset) = self.get(&index) {
+                // relative links in the directory need a trailing slash
+                return match ctx.path().ends_with('/') {
+                    true => Ok(self.serve(asset, ctx.req())),
+                    false => slash(ctx),
+                };
+            }
+        }
+
+        if let Some(asset) = self.fallback.as_deref().and_then(|path| self.get(path)) {
+            return Ok(self.serve(asset, ctx.req()));
+        }
+
+        ctx.next().await
+    }
+}
+
+/// Decode the request path into the asset key, None if it tries to escape
+fn normalize(path: &str) -> Option<String> {
+    let path = percent_encoding::percent_decode_str(path).decode_utf8().ok()?;
+    let mut parts = vec![];
+
+    for seg in path.split('/') {
+        match seg {
+            "" | "." => continue,
+            ".." => return None,
+            _ => parts.push(seg),
+        }
+    }
+
+    Some(parts.join("/"))
+}
+
+/// Collect the regular files recursively in a stable order, dotfiles are skipped
+fn collect(dir: &Path, files: &mut Vec<PathBuf>) -> FibraResult<()> {
+    let mut entries = std::fs::read_dir(dir)?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+
+        let path = entry.path();
+
+        match path.is_dir() {
+            true => collect(&path, files)?,
+            false if path.is_file() => files.push(path),
+            false => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Check if the content is worth compressing
+fn compressible(mime: &Mime) -> bool {
+    mime.type_() == mime::TEXT
+        || matches!(mime.subtype().as_str(), "javascript" | "json" | "xml" | "wasm")
+        || matches!(mime.suffix().map(|v| v.as_str()), Some("json" | "xml"))
+}
\ No newline at end of file
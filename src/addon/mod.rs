@@ -1,6 +1,14 @@
 //! Middlewares
+mod embed;
 mod logger;
 mod reqid;
+mod session;
+mod split;
+mod statics;
 
+pub use embed::*;
 pub use logger::*;
-pub use reqid::*;
\ No newline at end of file
+pub use reqid::*;
+pub use session::*;
+pub use split::*;
+pub use statics::*;
\ No newline at end of file
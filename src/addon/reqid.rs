@@ -2,7 +2,11 @@
 use crate::route::*;
 use crate::types::*;
 
-/// ReqID Middleware
+/// The generated request id stored in the context
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RequestId(pub String);
+
+/// ReqID Middleware, the id is stored in the context, the request header and the response header
 pub struct ReqID {
     header: String,
 }
@@ -30,8 +34,20 @@ impl ReqID {
     /// ```
     /// use fibra::*;
     ///
-    /// let mut app = Fibra::new();
-    /// app.mount(addon::ReqID::new().header("x-request-id"));
+    /// #[tokio::main]
+    /// async fn main() -> FibraResult<()> {
+    ///     let mut app = Fibra::new();
+    ///     app.mount(addon::ReqID::new().header("x-trace-id"));
+    ///     app.get("/", |ctx: Context| async move { ctx.get::<addon::RequestId>().map(|v| v.0.clone()).unwrap_or_default() })?;
+    ///
+    ///     let mut res = Context::from((app, Request::new())).next().await?;
+    ///     let id = res.header_ref("x-trace-id").map(|v| v.as_bytes().to_vec()).unwrap_or_default();
+    ///
+    ///     assert_eq!(id.len(), 26);
+    ///     assert_eq!(res.body_all().await.unwrap_or_default(), id);
+    ///
+    ///     Ok(())
+    /// }
     /// ```
     #[inline]
     pub fn header(mut self, header: impl Into<String>) -> Self {
@@ -50,9 +66,11 @@ impl Default for ReqID {
 #[async_trait]
 impl Handler for ReqID {
     async fn handle(&self, mut ctx: Context) -> FibraResult<Response> {
+        let id = ulid::Ulid::from_datetime(*ctx.created()).to_string();
         let key = HeaderName::try_from(self.header.as_str())?;
-        let val = HeaderValue::try_from(ulid::Ulid::from_datetime(*ctx.created()).to_string())?;
+        let val = HeaderValue::try_from(id.as_str())?;
 
+        ctx.insert(RequestId(id));
         ctx.req_mut().headers_mut().insert(key.clone(), val.clone());
         let mut res = ctx.next().await;
 
@@ -0,0 +1,480 @@
+//! Session Middleware
+use crate::route::*;
+use crate::types::*;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+/// Session values
+pub type SessionMap = HashMap<String, String>;
+
+/// Session storage backend, the store is responsible for dropping expired sessions
+#[async_trait]
+pub trait SessionStore: Send + Sync + 'static {
+    /// Load a session, None if it's absent or expired
+    async fn load(&self, id: &str) -> FibraResult<Option<SessionMap>>;
+
+    /// Save a session which expires after the ttl
+    async fn save(&self, id: &str, map: &SessionMap, ttl: Duration) -> FibraResult<()>;
+
+    /// Remove a session
+    async fn remove(&self, id: &str) -> FibraResult<()>;
+}
+
+/// The interval in seconds of sweeping expired sessions in the memory store
+const SWEEP_INTERVAL: u64 = 60;
+
+/// In-memory session store, sessions are lost when the process exits, expired sessions are
+/// swept once a minute at most
+#[derive(Default)]
+pub struct MemoryStore {
+    sessions: Mutex<HashMap<String, (SessionMap, SystemTime)>>,
+    swept: AtomicU64,
+}
+
+impl MemoryStore {
+    /// Create a new object
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl SessionStore for MemoryStore {
+    async fn load(&self, id: &str) -> FibraResult<Option<SessionMap>> {
+        let mut sessions = self.sessions.lock().map_err(|err| FibraError::PanicError(err.to_string().into()))?;
+
+        match sessions.get(id) {
+            Some((map, expires)) if *expires > SystemTime::now() => Ok(Some(map.clone())),
+            Some(_) => {
+                sessions.remove(id);
+                Ok(None)
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn save(&self, id: &str, map: &SessionMap, ttl: Duration) -> FibraResult<()> {
+        let mut sessions = self.sessions.lock().map_err(|err| FibraError::PanicError(err.to_string().into()))?;
+        let now = SystemTime::now();
+
+        // sweep expired sessions periodically to keep memory bounded
+        let secs = now.duration_since(UNIX_EPOCH)?.as_secs();
+
+        if secs.saturating_sub(self.swept.load(Ordering::Relaxed)) >= SWEEP_INTERVAL {
+            self.swept.store(secs, Ordering::Relaxed);
+            sessions.retain(|_, (_, expires)| *expires > now);
+        }
+
+        sessions.insert(id.to_string(), (map.clone(), now + ttl));
+
+        Ok(())
+    }
+
+    async fn remove(&self, id: &str) -> FibraResult<()> {
+        self.sessions.lock().map_err(|err| FibraError::PanicError(err.to_string().into()))?.remove(id);
+        Ok(())
+    }
+}
+
+/// File-backed session store, each session is a url-encoded file named by its id, the first
+/// line is the expiry time in unix seconds
+pub struct FileStore {
+    dir: PathBuf,
+}
+
+impl FileStore {
+    /// Create a new object, the directory is created if it doesn't exist
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    /// use fibra::addon::SessionStore;
+    /// use std::time::Duration;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> FibraResult<()> {
+    ///     let store = addon::FileStore::new(std::env::temp_dir().join("fibra-sessions"))?;
+    ///     let map = [("user".to_string(), "fibra & co".to_string())].into_iter().collect();
+    ///
+    ///     store.save("alive", &map, Duration::from_secs(60)).await?;
+    ///     store.save("expired", &map, Duration::ZERO).await?;
+    ///
+    ///     assert_eq!(store.load("alive").await?, Some(map));
+    ///     assert_eq!(store.load("expired").await?, None);
+    ///     assert_eq!(store.load("../alive").await?, None);
+    ///
+    ///     store.remove("alive").await?;
+    ///
+    ///     assert_eq!(store.load("alive").await?, None);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[inline]
+    pub fn new(dir: impl Into<PathBuf>) -> FibraResult<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    /// The file path of a session, ids with unexpected chars are refused
+    fn path(&self, id: &str) -> Option<PathBuf> {
+        match !id.is_empty() && id.bytes().all(|b| b.is_ascii_alphanumeric()) {
+            true => Some(self.dir.join(id)),
+            false => None,
+        }
+    }
+}
+
+#[async_trait]
+impl SessionStore for FileStore {
+    async fn load(&self, id: &str) -> FibraResult<Option<SessionMap>> {
+        let path = match self.path(id) {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+
+        let content = match tokio::fs::read_to_string(&path).await {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        let (expires, body) = content.split_once('\n').unwrap_or((content.as_str(), ""));
+
+        if expires.parse::<u64>().unwrap_or(0) <= SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() {
+            let _ = tokio::fs::remove_file(&path).await;
+            return Ok(None);
+        }
+
+        Ok(Some(form_urlencoded::parse(body.as_bytes()).into_owned().collect()))
+    }
+
+    async fn save(&self, id: &str, map: &SessionMap, ttl: Duration) -> FibraResult<()> {
+        let path = match self.path(id) {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        let expires = (SystemTime::now() + ttl).duration_since(UNIX_EPOCH)?.as_secs();
+        let body = form_urlencoded::Serializer::new(String::new()).extend_pairs(map.iter()).finish();
+
+        Ok(tokio::fs::write(path, format!("{}\n{}", expires, body)).await?)
+    }
+
+    async fn remove(&self, id: &str) -> FibraResult<()> {
+        if let Some(path) = self.path(id) {
+            match tokio::fs::remove_file(path).await {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => return Err(err.into()),
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The session of the current request, it's stored in the context by the Session middleware,
+/// changes are persisted after the handlers return
+///
+/// # Examples
+///
+/// ```
+/// use fibra::*;
+///
+/// async fn visit(ctx: Context) -> FibraResult<Response> {
+///     let session = ctx.get::<addon::SessionData>().cloned().ok_or(FibraError::PanicError("no session".into()))?;
+///     let count = session.get("count").and_then(|v| v.parse::<u32>().ok()).unwrap_or(0) + 1;
+///
+///     session.set("count", count.to_string());
+///
+///     Ok(count.to_string().into())
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SessionData(Arc<Mutex<SessionInner>>);
+
+#[derive(Debug, Default)]
+struct SessionInner {
+    id: String,
+    map: SessionMap,
+    fresh: bool,
+    modified: bool,
+    rotated: Option<String>,
+    destroyed: bool,
+}
+
+impl SessionData {
+    /// Lock the inner data, a poisoned lock is recovered since the data is plain values
+    fn lock(&self) -> std::sync::MutexGuard<'_, SessionInner> {
+        self.0.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// The session id
+    #[inline]
+    pub fn id(&self) -> String {
+        self.lock().id.clone()
+    }
+
+    /// Get a value
+    #[inline]
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.lock().map.get(key).cloned()
+    }
+
+    /// Set a value
+    #[inline]
+    pub fn set(&self, key: impl Into<String>, val: impl Into<String>) {
+        let mut inner = self.lock();
+        inner.map.insert(key.into(), val.into());
+        inner.modified = true;
+    }
+
+    /// Remove a value
+    #[inline]
+    pub fn remove(&self, key: &str) -> Option<String> {
+        let mut inner = self.lock();
+        inner.modified = true;
+        inner.map.remove(key)
+    }
+
+    /// Remove all values
+    #[inline]
+    pub fn clear(&self) {
+        let mut inner = self.lock();
+        inner.map.clear();
+        inner.modified = true;
+    }
+
+    /// Get all values
+    #[inline]
+    pub fn values(&self) -> SessionMap {
+        self.lock().map.clone()
+    }
+
+    /// Change the session id while keeping the values, call it after login to prevent
+    /// session fixation
+    #[inline]
+    pub fn rotate(&self) {
+        let mut inner = self.lock();
+        let old = std::mem::replace(&mut inner.id, ulid::Ulid::new().to_string());
+
+        if inner.rotated.is_none() && !inner.fresh {
+            inner.rotated = Some(old);
+        }
+
+        inner.modified = true;
+    }
+
+    /// Remove the session from the store and the client
+    #[inline]
+    pub fn destroy(&self) {
+        self.lock().destroyed = true;
+    }
+}
+
+/// Session Middleware, the session id is kept in a signed cookie and the values are kept in
+/// the store, sessions expire after the ttl since the last request, or since the last change
+/// if rolling is disabled
+pub struct Session {
+    key: Key,
+    store: Arc<dyn SessionStore>,
+    cookie: String,
+    ttl: Duration,
+    secure: bool,
+    rolling: bool,
+}
+
+impl Session {
+    /// Create a new object
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// async fn login(ctx: Context) -> FibraResult<Response> {
+    ///     let session = ctx.get::<addon::SessionData>().cloned().unwrap_or_default();
+    ///     session.rotate();
+    ///     session.set("user", ctx.query("user"));
+    ///     Ok(().into())
+    /// }
+    ///
+    /// async fn whoami(ctx: Context) -> FibraResult<Response> {
+    ///     Ok(ctx.get::<addon::SessionData>().and_then(|s| s.get("user")).unwrap_or_default().into())
+    /// }
+    ///
+    /// async fn logout(ctx: Context) -> FibraResult<Response> {
+    ///     ctx.get::<addon::SessionData>().map(|s| s.destroy());
+    ///     Ok(().into())
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> FibraResult<()> {
+    ///     let mut app = Fibra::new();
+    ///     app.mount(addon::Session::new(Key::generate(), addon::MemoryStore::new()));
+    ///     app.get("/login", login)?;
+    ///     app.get("/me", whoami)?;
+    ///     app.get("/out", logout)?;
+    ///
+    ///     let app = std::sync::Arc::new(app);
+    ///     let run = |uri: &'static str, cookie: String| {
+    ///         let req = Request::new().uri(uri).header(header::COOKIE, HeaderValue::try_from(cookie).unwrap_or(HeaderValue::from_static("")));
+    ///         Context::new(app.clone(), Default::default(), req).next()
+    ///     };
+    ///     let cookie = |res: &Response| res.header_ref(header::SET_COOKIE).and_then(|v| v.to_str().ok()).and_then(|v| v.split(';').next()).unwrap_or("").to_string();
+    ///
+    ///     let res = run("http://localip.cc/login?user=fibra", String::new()).await?;
+    ///     let sid = cookie(&res);
+    ///
+    ///     assert_eq!(sid.starts_with("fibra.sid="), true);
+    ///     assert_eq!(run("http://localip.cc/me", sid.clone()).await?.body_all().await.unwrap_or_default(), "fibra");
+    ///     assert_eq!(run("http://localip.cc/me", "fibra.sid=forged".to_string()).await?.body_all().await.unwrap_or_default(), "");
+    ///
+    ///     let res = run("http://localip.cc/out", sid.clone()).await?;
+    ///
+    ///     assert_eq!(cookie(&res), "fibra.sid=");
+    ///     assert_eq!(run("http://localip.cc/me", sid).await?.body_all().await.unwrap_or_default(), "");
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[inline]
+    pub fn new(key: Key, store: impl SessionStore) -> Self {
+        Self { key, store: Arc::new(store), cookie: "fibra.sid".to_string(), ttl: Duration::from_secs(86400), secure: false, rolling: true }
+    }
+
+    /// Set the cookie name, "fibra.sid" by default
+    #[inline]
+    pub fn cookie(mut self, name: impl Into<String>) -> Self {
+        self.cookie = name.into();
+        self
+    }
+
+    /// Set the session lifetime, one day by default
+    #[inline]
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Only send the cookie over https
+    #[inline]
+    pub fn secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    /// Extend the expiry of existing sessions on every request, enabled by default, the session
+    /// is saved and the cookie is sent even if nothing changes, disable it to make the expiry
+    /// absolute since the last change
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> FibraResult<()> {
+    ///     let run = |rolling: bool| async move {
+    ///         let mut app = Fibra::new();
+    ///         app.mount(addon::Session::new(Key::generate(), addon::MemoryStore::new()).rolling(rolling));
+    ///         app.get("/login", |ctx: Context| async move { ctx.get::<addon::SessionData>().map(|s| s.set("user", "fibra")); "" })?;
+    ///         app.get("/me", |_: Context| async move { "" })?;
+    ///
+    ///         let app = std::sync::Arc::new(app);
+    ///         let res = Context::new(app.clone(), Default::default(), Request::new().uri("http://localip.cc/login")).next().await?;
+    ///         let sid = res.header_ref(header::SET_COOKIE).and_then(|v| v.to_str().ok()).and_then(|v| v.split(';').next()).unwrap_or("").to_string();
+    ///         let req = Request::new().uri("http://localip.cc/me").header(header::COOKIE, HeaderValue::try_from(sid)?);
+    ///
+    ///         FibraResult::Ok(Context::new(app.clone(), Default::default(), req).next().await?.header_ref(header::SET_COOKIE).is_some())
+    ///     };
+    ///
+    ///     assert_eq!(run(true).await?, true);
+    ///     assert_eq!(run(false).await?, false);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[inline]
+    pub fn rolling(mut self, rolling: bool) -> Self {
+        self.rolling = rolling;
+        self
+    }
+
+    /// Build the session cookie
+    fn build(&self, id: String) -> Cookie<'static> {
+        Cookie::build((self.cookie.clone(), id))
+            .path("/")
+            .http_only(true)
+            .secure(self.secure)
+            .same_site(SameSite::Lax)
+            .max_age(::cookie::time::Duration::seconds(self.ttl.as_secs() as i64))
+            .build()
+    }
+}
+
+#[async_trait]
+impl Handler for Session {
+    async fn handle(&self, mut ctx: Context) -> FibraResult<Response> {
+        // only ids with a valid signature are looked up
+        let mut jar = CookieJar::new();
+
+        if let Some(val) = ctx.cookie(&self.cookie) {
+            jar.add_original(Cookie::new(self.cookie.clone(), val.to_string()));
+        }
+
+        let loaded = match jar.signed(&self.key).get(&self.cookie) {
+            Some(cookie) => self.store.load(cookie.value()).await?.map(|map| (cookie.value().to_string(), map)),
+            None => None,
+        };
+
+        let inner = match loaded {
+            Some((id, map)) => SessionInner { id, map, ..Default::default() },
+            None => SessionInner { id: ulid::Ulid::new().to_string(), fresh: true, ..Default::default() },
+        };
+
+        let session = SessionData(Arc::new(Mutex::new(inner)));
+        ctx.insert(session.clone());
+
+        let mut res = ctx.next().await?;
+        let (id, map, fresh, rotated, destroyed) = {
+            let inner = session.lock();
+
+            // untouched new sessions are never saved
+            if !inner.modified && !inner.destroyed && (inner.fresh || !self.rolling) {
+                return Ok(res);
+            }
+
+            (inner.id.clone(), inner.map.clone(), inner.fresh, inner.rotated.clone(), inner.destroyed)
+        };
+
+        if let Some(old) = rotated {
+            self.store.remove(&old).await?;
+        }
+
+        if destroyed {
+            if !fresh {
+                self.store.remove(&id).await?;
+            }
+
+            return Ok(res.remove_cookie(self.build(String::new())));
+        }
+
+        self.store.save(&id, &map, self.ttl).await?;
+
+        let mut jar = CookieJar::new();
+        jar.signed_mut(&self.key).add(self.build(id));
+
+        for cookie in jar.delta() {
+            res = res.cookie(cookie.clone());
+        }
+
+        Ok(res)
+    }
+}
\ No newline at end of file
@@ -0,0 +1,245 @@
+//! Split Middleware
+use crate::route::*;
+use crate::types::*;
+
+/// The key used to assign requests to variants, requests with the same key always go to the
+/// same variant, requests without the key are assigned randomly
+#[derive(Debug, Clone)]
+pub enum Sticky {
+    /// Assign randomly
+    Random,
+
+    /// Stick on a cookie's value
+    Cookie(String),
+
+    /// Stick on a header's value
+    Header(HeaderName),
+
+    /// Stick on the client's ip
+    RemoteIp,
+}
+
+/// The chosen variant stored in the context
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Variant(pub String);
+
+/// Split Middleware, send a percentage of requests to different handlers for canary and A/B
+/// routing, the chosen variant is stored in the context and the response header
+pub struct Split {
+    variants: Vec<(String, u32, BoxHandler)>,
+    sticky: Sticky,
+    header: HeaderName,
+    queries: Vec<String>,
+    headers: Vec<HeaderName>,
+}
+
+impl Split {
+    /// Create a new object
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> FibraResult<()> {
+    ///     let mut app = Fibra::new();
+    ///     app.get("/", addon::Split::new().sticky(addon::Sticky::RemoteIp).variant("stable", 90, "v1").variant("canary", 10, "v2"))?;
+    ///
+    ///     let mut res = Context::from((app, Request::new())).next().await?;
+    ///     let variant = res.header_ref("x-variant").map(|v| v.as_bytes().to_vec()).unwrap_or_default();
+    ///     let body = res.body_all().await.unwrap_or_default();
+    ///
+    ///     assert_eq!((variant.as_slice(), body.as_ref()) == (b"stable", b"v1") || (variant.as_slice(), body.as_ref()) == (b"canary", b"v2"), true);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[inline]
+    pub fn new() -> Self {
+        Self { variants: vec![], sticky: Sticky::Random, header: HeaderName::from_static("x-variant"), queries: vec![], headers: vec![] }
+    }
+
+    /// Add a variant with its weight, variants with zero weight can only be chosen by overrides
+    #[inline]
+    pub fn variant<M>(mut self, name: impl Into<String>, weight: u32, handler: impl IntoHandler<M>) -> Self {
+        self.variants.push((name.into(), weight, Box::new(handler.into_handler())));
+        self
+    }
+
+    /// Set the sticky key
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> FibraResult<()> {
+    ///     let mut app = Fibra::new();
+    ///     app.get("/", addon::Split::new().sticky(addon::Sticky::Cookie("uid".to_string())).variant("a", 50, "a").variant("b", 50, "b"))?;
+    ///
+    ///     let app = std::sync::Arc::new(app);
+    ///     let run = |uid: &'static str| Context::new(app.clone(), Default::default(), Request::new().header(header::COOKIE, uid)).next();
+    ///     let first = run("uid=12345").await?.body_all().await.unwrap_or_default();
+    ///
+    ///     for _ in 0..10 {
+    ///         assert_eq!(run("uid=12345").await?.body_all().await.unwrap_or_default(), first);
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[inline]
+    pub fn sticky(mut self, sticky: Sticky) -> Self {
+        self.sticky = sticky;
+        self
+    }
+
+    /// Set the header name which records the chosen variant
+    #[inline]
+    pub fn header(mut self, header: impl IntoHeaderName) -> Self {
+        self.header = header.into_header_name();
+        self
+    }
+
+    /// Allow choosing a variant by the query's value, useful for QA
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> FibraResult<()> {
+    ///     let mut app = Fibra::new();
+    ///     app.get("/", addon::Split::new().override_query("variant").variant("stable", 100, "v1").variant("canary", 0, |ctx: Context| async move {
+    ///         format!("v2 {}", ctx.get::<addon::Variant>().map(|v| v.0.as_str()).unwrap_or(""))
+    ///     }))?;
+    ///
+    ///     let app = std::sync::Arc::new(app);
+    ///     let run = |uri: &'static str| Context::new(app.clone(), Default::default(), Request::new().uri(uri)).next();
+    ///
+    ///     assert_eq!(run("http://localip.cc/").await?.body_all().await.unwrap_or_default(), "v1");
+    ///     assert_eq!(run("http://localip.cc/?variant=canary").await?.body_all().await.unwrap_or_default(), "v2 canary");
+    ///     assert_eq!(run("http://localip.cc/?variant=unknown").await?.body_all().await.unwrap_or_default(), "v1");
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[inline]
+    pub fn override_query(mut self, key: impl Into<String>) -> Self {
+        self.queries.push(key.into());
+        self
+    }
+
+    /// Allow choosing a variant by the header's value, useful for QA
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> FibraResult<()> {
+    ///     let mut app = Fibra::new();
+    ///     app.get("/", addon::Split::new().override_header("x-force-variant").variant("stable", 100, "v1").variant("canary", 0, "v2"))?;
+    ///
+    ///     let req = Request::new().header("x-force-variant", "canary");
+    ///     let mut res = Context::from((app, req)).next().await?;
+    ///
+    ///     assert_eq!(res.header_ref("x-variant").map(|v| v.as_bytes()), Some("canary".as_bytes()));
+    ///     assert_eq!(res.body_all().await.unwrap_or_default(), "v2");
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[inline]
+    pub fn override_header(mut self, key: impl IntoHeaderName) -> Self {
+        self.headers.push(key.into_header_name());
+        self
+    }
+
+    /// Get all variants
+    #[inline]
+    pub fn variants(&self) -> &Vec<(String, u32, BoxHandler)> {
+        &self.variants
+    }
+
+    /// Choose a variant for the request
+    pub fn choose(&self, ctx: &Context) -> Option<usize> {
+        // overrides take precedence over weights
+        let forced = self.queries.iter().map(|key| ctx.query(key).as_bytes())
+            .chain(self.headers.iter().filter_map(|key| ctx.header(key).map(|v| v.as_bytes())))
+            .find_map(|name| self.variants.iter().position(|(v, _, _)| v.as_bytes() == name));
+
+        if forced.is_some() {
+            return forced;
+        }
+
+        let total: u64 = self.variants.iter().map(|(_, weight, _)| *weight as u64).sum();
+
+        if total == 0 {
+            return None;
+        }
+
+        let mut point = self.hash(ctx) % total;
+
+        self.variants.iter().position(|(_, weight, _)| {
+            match point < *weight as u64 {
+                true => true,
+                false => {
+                    point -= *weight as u64;
+                    false
+                }
+            }
+        })
+    }
+
+    /// Hash the sticky key, fnv-1a is used to keep the result stable across restarts
+    fn hash(&self, ctx: &Context) -> u64 {
+        let key: Option<Cow<[u8]>> = match &self.sticky {
+            Sticky::Random => None,
+            Sticky::Cookie(name) => ctx.cookie(name).map(|v| Cow::Borrowed(v.as_bytes())),
+            Sticky::Header(name) => ctx.header(name).map(|v| Cow::Borrowed(v.as_bytes())),
+            Sticky::RemoteIp => Some(Cow::Owned(ctx.remote().ip().to_canonical().to_string().into_bytes())),
+        };
+
+        match key {
+            Some(key) => key.iter().fold(0xcbf29ce484222325u64, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3)),
+            None => {
+                use std::hash::BuildHasher;
+                std::collections::hash_map::RandomState::new().hash_one(ctx.created())
+            }
+        }
+    }
+}
+
+impl Default for Split {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Handler for Split {
+    async fn handle(&self, mut ctx: Context) -> FibraResult<Response> {
+        let index = match self.choose(&ctx) {
+            Some(index) => index,
+            None => return ctx.next().await,
+        };
+
+        let (name, _, handler) = &self.variants[index];
+        let val = HeaderValue::try_from(name.as_str())?;
+
+        ctx.insert(Variant(name.clone()));
+        let mut res = handler.handle(ctx).await;
+
+        if let Ok(res) = &mut res {
+            res.headers_mut().insert(self.header.clone(), val);
+        }
+
+        res
+    }
+}
\ No newline at end of file
@@ -0,0 +1,495 @@
+//! Static Files
+use crate::route::*;
+use crate::types::*;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// The precompressed encodings in order of preference and their file extensions
+pub(crate) const ENCODINGS: [(&str, &str); 2] = [("br", "br"), ("gzip", "gz")];
+
+/// The chars to be escaped in the links of listings, unreserved chars are kept
+const SEGMENT: &percent_encoding::AsciiSet = &percent_encoding::NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_').remove(b'~');
+
+/// How symbolic links under the root are treated
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SymlinkPolicy {
+    /// Follow all links, even those pointing outside the root
+    Follow,
+
+    /// Refuse any path which passes through a link
+    Deny,
+
+    /// Follow links only if their targets are inside the root
+    #[default]
+    WithinRoot,
+}
+
+/// Serve files under a directory, the path after the router's prefix or the glob param is mapped
+/// into the directory, requests which escape the root are never served
+///
+/// # Examples
+///
+/// ```
+/// use fibra::*;
+///
+/// #[tokio::main]
+/// async fn main() -> FibraResult<()> {
+///     let root = std::env::temp_dir().join("fibra-static");
+///     std::fs::create_dir_all(root.join("docs"))?;
+///     std::fs::write(root.join("index.html"), "<h1>Home</h1>")?;
+///     std::fs::write(root.join("app.js"), "console.log(1)")?;
+///     std::fs::write(root.join("app.js.gz"), "gzipped")?;
+///     std::fs::write(root.join("docs").join("a.txt"), "a")?;
+///     std::fs::write(root.join(".env"), "secret")?;
+///
+///     let get = |uri: &'static str| {
+///         let mut app = Fibra::new();
+///         app.mount(addon::Static::new(&root).listing(true).precompressed(true));
+///         Context::from((app, Request::new().uri(uri).header(header::ACCEPT_ENCODING, "gzip")))
+///     };
+///
+///     // index file
+///     let mut res = get("http://localhost/").next().await?;
+///     assert_eq!(res.status_ref(), &Status::OK);
+///     assert_eq!(res.body_all().await.unwrap_or_default(), "<h1>Home</h1>");
+///
+///     // precompressed sibling
+///     let mut res = get("http://localhost/app.js").next().await?;
+///     assert_eq!(res.header_ref(header::CONTENT_ENCODING).map(|v| v.as_bytes()), Some("gzip".as_bytes()));
+///     assert_eq!(res.header_ref(header::CONTENT_TYPE).map(|v| v.as_bytes()), Some("text/javascript".as_bytes()));
+///     assert_eq!(res.header_ref(header::VARY).map(|v| v.as_bytes()), Some("accept-encoding".as_bytes()));
+///     assert_eq!(res.body_all().await.unwrap_or_default(), "gzipped");
+///
+///     // directory without trailing slash
+///     let res = get("http://localhost/docs?page=1").next().await?;
+///     assert_eq!(res.status_ref(), &Status::MOVED_PERMANENTLY);
+///     assert_eq!(res.header_ref(header::LOCATION).map(|v| v.as_bytes()), Some("/docs/?page=1".as_bytes()));
+///
+///     // no protocol-relative redirects
+///     let res = get("http://localhost//docs").next().await?;
+///     assert_eq!(res.header_ref(header::LOCATION).map(|v| v.as_bytes()), Some("/docs/".as_bytes()));
+///
+///     // directory listing
+///     let mut res = get("http://localhost/docs/").next().await?;
+///     assert_eq!(String::from_utf8_lossy(&res.body_all().await.unwrap_or_default()).contains("<a href=\"a.txt\">a.txt</a>"), true);
+///
+///     // traversal and dotfiles
+///     assert_eq!(get("http://localhost/docs/..%2f.env").next().await?.status_ref(), &Status::NOT_FOUND);
+///     assert_eq!(get("http://localhost/.env").next().await?.status_ref(), &Status::NOT_FOUND);
+///
+///     Ok(())
+/// }
+/// ```
+pub struct Static {
+    root: PathBuf,
+    index: Option<String>,
+    listing: bool,
+    precompressed: bool,
+    fallback: Option<PathBuf>,
+    symlinks: SymlinkPolicy,
+    dotfiles: bool,
+}
+
+impl Static {
+    /// Create a new object, index.html is served for directories by default
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let mut app = Fibra::new();
+    /// app.mount(addon::Static::new("./public"));
+    /// ```
+    #[inline]
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            index: Some("index.html".to_string()),
+            listing: false,
+            precompressed: false,
+            fallback: None,
+            symlinks: SymlinkPolicy::default(),
+            dotfiles: false,
+        }
+    }
+
+    /// Set the index file of directories, None to disable it
+    #[inline]
+    pub fn index(mut self, index: Option<&str>) -> Self {
+        self.index = index.map(|v| v.to_string());
+        self
+    }
+
+    /// List the entries of directories without an index file, json is replied if the client
+    /// prefers it over html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> FibraResult<()> {
+    ///     let root = std::env::temp_dir().join("fibra-static-listing");
+    ///     std::fs::create_dir_all(root.join("sub"))?;
+    ///     std::fs::write(root.join("b & c.txt"), "bc")?;
+    ///
+    ///     let get = |req: Request| {
+    ///         let mut app = Fibra::new();
+    ///         app.mount(addon::Static::new(&root).index(None).listing(true));
+    ///         Context::from((app, req.uri("http://localhost/")))
+    ///     };
+    ///
+    ///     let mut res = get(Request::new()).next().await?;
+    ///     let html = String::from_utf8_lossy(&res.body_all().await.unwrap_or_default()).to_string();
+    ///
+    ///     assert_eq!(res.header_ref(header::CONTENT_TYPE).map(|v| v.as_bytes()), Some(mime::TEXT_HTML_UTF_8.as_ref().as_bytes()));
+    ///     assert_eq!(html.contains("<a href=\"b%20%26%20c.txt\">b &amp; c.txt</a>"), true);
+    ///     assert_eq!(html.find("sub/") < html.find("b &amp; c.txt"), true);
+    ///
+    ///     let mut res = get(Request::new().header(header::ACCEPT, "application/json")).next().await?;
+    ///     let json = String::from_utf8_lossy(&res.body_all().await.unwrap_or_default()).to_string();
+    ///
+    ///     assert_eq!(json.starts_with("[{\"name\":\"sub\",\"dir\":true,"), true);
+    ///     assert_eq!(json.contains("{\"name\":\"b & c.txt\",\"dir\":false,\"size\":2,"), true);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[inline]
+    pub fn listing(mut self, listing: bool) -> Self {
+        self.listing = listing;
+        self
+    }
+
+    /// Serve the .br or .gz sibling of a file if the client accepts the encoding
+    #[inline]
+    pub fn precompressed(mut self, precompressed: bool) -> Self {
+        self.precompressed = precompressed;
+        self
+    }
+
+    /// Serve a file instead when nothing matches, e.g. the index.html of a single page app
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> FibraResult<()> {
+    ///     let root = std::env::temp_dir().join("fibra-static-spa");
+    ///     std::fs::create_dir_all(&root)?;
+    ///     std::fs::write(root.join("index.html"), "<div id=\"app\"></div>")?;
+    ///
+    ///     let mut app = Fibra::new();
+    ///     app.mount(addon::Static::new(&root).fallback(root.join("index.html")));
+    ///
+    ///     let mut res = Context::from((app, Request::new().uri("http://localhost/users/42"))).next().await?;
+    ///
+    ///     assert_eq!(res.status_ref(), &Status::OK);
+    ///     assert_eq!(res.body_all().await.unwrap_or_default(), "<div id=\"app\"></div>");
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[inline]
+    pub fn fallback(mut self, file: impl Into<PathBuf>) -> Self {
+        self.fallback = Some(file.into());
+        self
+    }
+
+    /// Set how symbolic links are treated, links are followed within the root by default
+    #[inline]
+    pub fn symlinks(mut self, policy: SymlinkPolicy) -> Self {
+        self.symlinks = policy;
+        self
+    }
+
+    /// Serve files and directories whose names start with a dot, disabled by default
+    #[inline]
+    pub fn dotfiles(mut self, dotfiles: bool) -> Self {
+        self.dotfiles = dotfiles;
+        self
+    }
+
+    /// Map the request path into the root, None if it tries to escape
+    fn resolve(&self, path: &str) -> Option<PathBuf> {
+        let path = percent_encoding::percent_decode_str(path).decode_utf8().ok()?;
+        let mut real = self.root.clone();
+
+        for seg in path.split('/') {
+            match seg {
+                "" | "." => continue,
+                ".." => return None,
+                _ if seg.contains(['\\', '\0']) => return None,
+                _ if seg.starts_with('.') && !self.dotfiles => return None,
+                _ => {}
+            }
+
+            // reject drive letters and other prefixes
+            let mut parts = Path::new(seg).components();
+
+            match (parts.next(), parts.next()) {
+                (Some(Component::Normal(_)), None) => real.push(seg),
+                _ => return None,
+            }
+        }
+
+        Some(real)
+    }
+
+    /// Check the path against the symlink policy, missing paths are permitted
+    async fn permitted(&self, path: &Path) -> FibraResult<bool> {
+        let missing = |err: &std::io::Error| matches!(err.kind(), std::io::ErrorKind::NotFound | std::io::ErrorKind::NotADirectory);
+
+        match self.symlinks {
+            SymlinkPolicy::Follow => Ok(true),
+            SymlinkPolicy::Deny => {
+                let mut real = self.root.clone();
+
+                for part in path.strip_prefix(&self.root).unwrap_or(path).components() {
+                    real.push(part);
+
+                    match tokio::fs::symlink_metadata(&real).await {
+                        Ok(meta) if meta.file_type().is_symlink() => return Ok(false),
+                        Ok(_) => {}
+                        Err(err) if missing(&err) => return Ok(true),
+                        Err(err) => return Err(err.into()),
+                    }
+                }
+
+                Ok(true)
+            }
+            SymlinkPolicy::WithinRoot => {
+                let root = tokio::fs::canonicalize(&self.root).await?;
+
+                match tokio::fs::canonicalize(path).await {
+                    Ok(real) => Ok(real.starts_with(root)),
+                    Err(err) if missing(&err) => Ok(true),
+                    Err(err) => Err(err.into()),
+                }
+            }
+        }
+    }
+
+    /// Serve a file or its precompressed sibling, None if it isn't a regular file
+    async fn serve(&self, path: &Path, req: &Request) -> FibraResult<Option<Response>> {
+        let entity = match Entity::open(path, None).await? {
+            Some(entity) => entity,
+            None => return Ok(None),
+        };
+
+        if !self.precompressed {
+            return Ok(Some(entity.respond(Response::new(), Some(req))));
+        }
+
+        let res = Response::new().header(header::VARY, "accept-encoding");
+
+        for (encoding, ext) in accepted(req) {
+            let mut name = path.as_os_str().to_owned();
+            name.push(".");
+            name.push(ext);
+
+            let variant = PathBuf::from(name);
+
+            if !self.permitted(&variant).await? {
+                continue;
+            }
+
+            if let Some(entity) = Entity::open(&variant, Some(path)).await? {
+                return Ok(Some(entity.encoding(encoding).respond(res, Some(req))));
+            }
+        }
+
+        Ok(Some(entity.respond(res, Some(req))))
+    }
+
+    /// List the directory in html or json
+    async fn list(&self, dir: &Path, req: &Request) -> FibraResult<Response> {
+        let mut entries = vec![];
+        let mut reader = tokio::fs::read_dir(dir).await?;
+
+        while let Some(entry) = reader.next_entry().await? {
+            let name = match entry.file_name().into_string() {
+                Ok(name) => name,
+                Err(_) => continue,
+            };
+
+            if name.starts_with('.') && !self.dotfiles || !self.permitted(&entry.path()).await? {
+                continue;
+            }
+
+            // broken links are skipped
+            let meta = match tokio::fs::metadata(entry.path()).await {
+                Ok(meta) => meta,
+                Err(_) => continue,
+            };
+
+            let modified = meta.modified().ok().and_then(|v| v.duration_since(UNIX_EPOCH).ok()).map(|v| v.as_secs());
+            entries.push((name, meta.is_dir(), meta.len(), modified));
+        }
+
+        entries.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        if req.accept_quality(&mime::APPLICATION_JSON) > req.accept_quality(&mime::TEXT_HTML) {
+            let items: Vec<IndexMap<&str, &dyn encoder::json::Encode>> = entries.iter().map(|(name, dir, size, modified)| {
+                let mut item: IndexMap<&str, &dyn encoder::json::Encode> = IndexMap::new();
+                item.insert("name", name);
+                item.insert("dir", dir);
+                item.insert("size", size);
+                item.insert("modified", modified);
+                item
+            }).collect();
+
+            return Ok(Response::new().json(items));
+        }
+
+        let title = escape(&percent_encoding::percent_decode_str(req.uri_ref().path()).decode_utf8_lossy());
+        let mut html = format!("<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>Index of {0}</title></head>\n<body>\n<h1>Index of {0}</h1>\n<ul>\n", title);
+
+        for (name, dir, _, _) in &entries {
+            let slash = if *dir { "/" } else { "" };
+            let href = percent_encoding::utf8_percent_encode(name, SEGMENT);
+            html.push_str(&format!("<li><a href=\"{}{}\">{}{}</a></li>\n", href, slash, escape(name), slash));
+        }
+
+        html.push_str("</ul>\n</body>\n</html>\n");
+
+        Ok(Response::new().html(html))
+    }
+
+    /// Serve the fallback file or pass to the next handler
+    async fn missing(&self, ctx: Context) -> FibraResult<Response> {
+        if let Some(file) = &self.fallback {
+            if let Some(res) = self.serve(file, ctx.req()).await? {
+                return Ok(res);
+            }
+        }
+
+        ctx.next().await
+    }
+}
+
+#[async_trait]
+impl Handler for Static {
+    async fn handle(&self, ctx: Context) -> FibraResult<Response> {
+        if !matches!(*ctx.method(), Method::GET | Method::HEAD) {
+            return ctx.next().await;
+        }
+
+        let path = match self.resolve(&rest(&ctx)) {
+            Some(path) => path,
+            None => return ctx.next().await,
+        };
+
+        if !self.permitted(&path).await? {
+            return self.missing(ctx).await;
+        }
+
+        let meta = match tokio::fs::metadata(&path).await {
+            Ok(meta) => meta,
+            Err(err) if matches!(err.kind(), std::io::ErrorKind::NotFound | std::io::ErrorKind::NotADirectory) => return self.missing(ctx).await,
+            Err(err) => return Err(err.into()),
+        };
+
+        if !meta.is_dir() {
+            return match self.serve(&path, ctx.req()).await? {
+                Some(res) => Ok(res),
+                None => self.missing(ctx).await,
+            };
+        }
+
+        // relative links in the directory need a trailing slash
+        if !ctx.path().ends_with('/') {
+            return slash(ctx);
+        }
+
+        if let Some(index) = &self.index {
+            let index = path.join(index);
+
+            if self.permitted(&index).await? {
+                if let Some(res) = self.serve(&index, ctx.req()).await? {
+                    return Ok(res);
+                }
+            }
+        }
+
+        match self.listing {
+            true => self.list(&path, ctx.req()).await,
+            false => self.missing(ctx).await,
+        }
+    }
+}
+
+/// The path after the router's prefix or the glob param, still percent-encoded
+pub(crate) fn rest(ctx: &Context) -> String {
+    match ctx.params().get("*") {
+        Some(rest) => rest.clone(),
+        None => String::from_utf8_lossy(ctx.path().as_bytes().strip_prefix(ctx.prefix()).unwrap_or_default()).to_string(),
+    }
+}
+
+/// Redirect to the path with a trailing slash, the query is kept, leading slashes are collapsed
+/// so the location can't be read as a protocol-relative url like "//host/dir/"
+pub(crate) fn slash(ctx: Context) -> FibraResult<Response> {
+    let path = ctx.path().trim_start_matches(['/', '\\']);
+    let location = match ctx.req().uri_ref().query() {
+        Some(query) => format!("/{}/?{}", path, query),
+        None => format!("/{}/", path),
+    };
+
+    ctx.redirect(Uri::try_from(location)?, Redirect::MovedPermanently301)
+}
+
+/// The precompressed encodings accepted by the client, ordered by quality
+pub(crate) fn accepted(req: &Request) -> Vec<(&'static str, &'static str)> {
+    let mut quality = [None; ENCODINGS.len()];
+    let mut star = None;
+
+    for value in req.headers_ref().get_all(header::ACCEPT_ENCODING) {
+        let value = match value.to_str() {
+            Ok(value) => value,
+            Err(_) => continue,
+        };
+
+        for item in value.split(',') {
+            let mut parts = item.split(';');
+            let name = parts.next().unwrap_or_default().trim();
+            let q = parts.find_map(|v| v.trim().strip_prefix("q=")).and_then(|v| v.trim().parse::<f32>().ok()).unwrap_or(1.0);
+
+            match ENCODINGS.iter().position(|(encoding, _)| encoding.eq_ignore_ascii_case(name)) {
+                Some(pos) => quality[pos] = Some(q),
+                None if name == "*" => star = Some(q),
+                None => {}
+            }
+        }
+    }
+
+    let mut found: Vec<_> = ENCODINGS.iter().zip(quality)
+        .filter_map(|(&encoding, q)| q.or(star).filter(|q| *q > 0.0).map(|q| (encoding, q)))
+        .collect();
+
+    found.sort_by(|a, b| b.1.total_cmp(&a.1));
+    found.into_iter().map(|(encoding, _)| encoding).collect()
+}
+
+/// Escape the html special chars
+fn escape(text: &str) -> String {
+    let mut buf = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => buf.push_str("&amp;"),
+            '<' => buf.push_str("&lt;"),
+            '>' => buf.push_str("&gt;"),
+            '"' => buf.push_str("&quot;"),
+            '\'' => buf.push_str("&#39;"),
+            _ => buf.push(c),
+        }
+    }
+
+    buf
+}
\ No newline at end of file
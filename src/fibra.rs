@@ -25,6 +25,16 @@ pub struct Fibra {
     /// added as handlers.
     mounted: Vec<BoxHandler>,
 
+    /// States is used to store shared data like database pools, configs and caches. Handlers
+    /// can access them through the context, subrouters inherit states from their parents.
+    states: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+
+    /// Body limit is used to restrict the request body size and the waiting time of each read,
+    /// nested routers can only tighten the limits of their parents. The root router has 16 MiB
+    /// and 30 seconds by default when running.
+    body_limit: Option<usize>,
+    read_timeout: Option<std::time::Duration>,
+
     /// Sockets is used to store all TCP listeners. We support listening on multiple addresses
     /// simultaneously. You can achieve this by calling the **bind** method multiple times.
     sockets: Vec<Socket>,
@@ -39,7 +49,7 @@ impl Fibra {
 
     /// Register a route for GET method
     #[inline]
-    pub fn get(&mut self, path: impl Into<Bytes>, handler: impl Handler) -> FibraResult<&mut Routine> {
+    pub fn get<M>(&mut self, path: impl Into<Bytes>, handler: impl IntoHandler<M>) -> FibraResult<&mut Routine> {
         let routine = self.route(path, handler)?;
         routine.limit().method(Method::GET);
         Ok(routine)
@@ -47,7 +57,7 @@ impl Fibra {
 
     /// Register a route for POST method
     #[inline]
-    pub fn post(&mut self, path: impl Into<Bytes>, handler: impl Handler) -> FibraResult<&mut Routine> {
+    pub fn post<M>(&mut self, path: impl Into<Bytes>, handler: impl IntoHandler<M>) -> FibraResult<&mut Routine> {
         let routine = self.route(path, handler)?;
         routine.limit().method(Method::POST);
         Ok(routine)
@@ -55,7 +65,7 @@ impl Fibra {
 
     /// Register a route for PUT method
     #[inline]
-    pub fn put(&mut self, path: impl Into<Bytes>, handler: impl Handler) -> FibraResult<&mut Routine> {
+    pub fn put<M>(&mut self, path: impl Into<Bytes>, handler: impl IntoHandler<M>) -> FibraResult<&mut Routine> {
         let routine = self.route(path, handler)?;
         routine.limit().method(Method::PUT);
         Ok(routine)
@@ -63,7 +73,7 @@ impl Fibra {
 
     /// Register a route for DELETE method
     #[inline]
-    pub fn delete(&mut self, path: impl Into<Bytes>, handler: impl Handler) -> FibraResult<&mut Routine> {
+    pub fn delete<M>(&mut self, path: impl Into<Bytes>, handler: impl IntoHandler<M>) -> FibraResult<&mut Routine> {
         let routine = self.route(path, handler)?;
         routine.limit().method(Method::DELETE);
         Ok(routine)
@@ -71,7 +81,7 @@ impl Fibra {
 
     /// Register a route for PATCH method
     #[inline]
-    pub fn patch(&mut self, path: impl Into<Bytes>, handler: impl Handler) -> FibraResult<&mut Routine> {
+    pub fn patch<M>(&mut self, path: impl Into<Bytes>, handler: impl IntoHandler<M>) -> FibraResult<&mut Routine> {
         let routine = self.route(path, handler)?;
         routine.limit().method(Method::PATCH);
         Ok(routine)
@@ -79,7 +89,7 @@ impl Fibra {
 
     /// Register a route for all methods
     #[inline]
-    pub fn all(&mut self, path: impl Into<Bytes>, handler: impl Handler) -> FibraResult<&mut Routine> {
+    pub fn all<M>(&mut self, path: impl Into<Bytes>, handler: impl IntoHandler<M>) -> FibraResult<&mut Routine> {
         self.route(path, handler)
     }
 
@@ -106,7 +116,7 @@ impl Fibra {
     ///     Ok(())
     /// }
     /// ```
-    pub fn route(&mut self, path: impl Into<Bytes>, handler: impl Handler) -> FibraResult<&mut Routine> {
+    pub fn route<M>(&mut self, path: impl Into<Bytes>, handler: impl IntoHandler<M>) -> FibraResult<&mut Routine> {
         let mut path = path.into();
 
         if !self.initial.is_empty() {
@@ -119,7 +129,7 @@ impl Fibra {
             path = data.freeze();
         }
 
-        self.ensure::<Matcher>().insert(path, handler)
+        self.ensure::<Matcher>().insert(path, handler.into_handler())
     }
 
     /// Register a subrouter
@@ -262,6 +272,114 @@ impl Fibra {
         self
     }
 
+    /// Store a shared state, the old state with the same type will be replaced
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> FibraResult<()> {
+    ///     struct Config { name: &'static str }
+    ///
+    ///     let mut app = Fibra::new();
+    ///     app.state(Config { name: "root" });
+    ///     app.get("/", |ctx: Context| async move { ctx.state::<Config>().map(|c| c.name).unwrap_or("") })?;
+    ///
+    ///     let v1 = app.group("/v1")?;
+    ///     v1.get("/u", |ctx: Context| async move { ctx.state::<Config>().map(|c| c.name).unwrap_or("") })?;
+    ///
+    ///     let v2 = app.group("/v2")?;
+    ///     v2.state(Config { name: "v2" });
+    ///     v2.get("/u", |ctx: Context| async move { ctx.state::<Config>().map(|c| c.name).unwrap_or("") })?;
+    ///
+    ///     let app = std::sync::Arc::new(app);
+    ///     let run = |uri: &'static str| Context::new(app.clone(), Default::default(), Request::new().uri(uri)).next();
+    ///
+    ///     assert_eq!(run("http://localip.cc/").await?.body_all().await.unwrap_or_default(), "root");
+    ///     assert_eq!(run("http://localip.cc/v1/u").await?.body_all().await.unwrap_or_default(), "root");
+    ///     assert_eq!(run("http://localip.cc/v2/u").await?.body_all().await.unwrap_or_default(), "v2");
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[inline]
+    pub fn state<T: Send + Sync + 'static>(&mut self, val: T) -> &mut Self {
+        self.states.insert(TypeId::of::<T>(), Box::new(val));
+        self
+    }
+
+    /// Limit the request body size, requests with a larger Content-Length are rejected with
+    /// PAYLOAD_TOO_LARGE once a route matches and before reading the body, so clients sending
+    /// Expect: 100-continue won't upload it, bodies without Content-Length fail when the limit
+    /// is exceeded, subrouters can only lower the limit of their parents
+    ///
+    /// The upfront check only applies to routes, mounted handlers like Static, Embed or custom
+    /// middlewares get no early rejection and only fail when they read past the limit
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> FibraResult<()> {
+    ///     let mut app = Fibra::new();
+    ///     app.max_body_size(8).catch(|res, _| res);
+    ///     app.post("/", |mut ctx: Context| async move { ctx.try_read_all().await.map(|body| Response::new().body(body)) })?;
+    ///
+    ///     // the root limit still applies
+    ///     let big = app.group("/big")?;
+    ///     big.max_body_size(1024);
+    ///     big.post("/", |mut ctx: Context| async move { ctx.try_read_all().await.map(|body| Response::new().body(body)) })?;
+    ///
+    ///     let app = std::sync::Arc::new(app);
+    ///     let run = |req: Request| Context::new(app.clone(), Default::default(), req.method(Method::POST)).next();
+    ///
+    ///     assert_eq!(run(Request::new().body("fibra")).await?.body_all().await.unwrap_or_default(), "fibra");
+    ///     assert_eq!(run(Request::new().body("Hello World!")).await?.status_ref(), &Status::PAYLOAD_TOO_LARGE);
+    ///     assert_eq!(run(Request::new().uri("http://localip.cc/big").body("Hello World!")).await?.status_ref(), &Status::PAYLOAD_TOO_LARGE);
+    ///
+    ///     let req = || Request::new().header(header::CONTENT_LENGTH, "1024").header(header::EXPECT, "100-continue");
+    ///
+    ///     assert_eq!(run(req()).await?.status_ref(), &Status::PAYLOAD_TOO_LARGE);
+    ///     assert_eq!(run(req().uri("http://localip.cc/unknown")).await?.status_ref(), &Status::NOT_FOUND);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[inline]
+    pub fn max_body_size(&mut self, max: usize) -> &mut Self {
+        self.body_limit = Some(max);
+        self
+    }
+
+    /// Limit the waiting time of each body read, slow clients are rejected with REQUEST_TIMEOUT
+    #[inline]
+    pub fn read_timeout(&mut self, timeout: std::time::Duration) -> &mut Self {
+        self.read_timeout = Some(timeout);
+        self
+    }
+
+    /// Get a shared state of this router, parents' states are not included
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let mut app = Fibra::new();
+    /// app.state(12345u32);
+    ///
+    /// assert_eq!(app.state_ref::<u32>(), Some(&12345));
+    /// assert_eq!(app.state_ref::<u64>(), None);
+    /// ```
+    #[inline]
+    pub fn state_ref<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        self.states.get(&TypeId::of::<T>()).and_then(|val| val.downcast_ref())
+    }
+
     /// Ensure the last item is type T, otherwise create it
     ///
     /// # Examples
@@ -291,6 +409,27 @@ impl Fibra {
         &self.mounted
     }
 
+    /// Get the path prefix of this router, it's set by `group`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// fn main() -> FibraResult<()> {
+    ///     let mut app = Fibra::new();
+    ///
+    ///     assert_eq!(app.prefix(), "");
+    ///     assert_eq!(app.group("/api/")?.group("/v1")?.prefix(), "/api/v1");
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[inline]
+    pub fn prefix(&self) -> &Bytes {
+        &self.initial
+    }
+
     /// Bind tcp listener to a local address, calling this multiple times to listening on multiple addresses
     ///
     /// # Examples
@@ -323,6 +462,10 @@ impl Fibra {
         // root router must have a catcher
         self.catcher.get_or_insert(Catcher::new());
 
+        // root router protects bodies by default
+        self.body_limit.get_or_insert(BODY_LIMIT);
+        self.read_timeout.get_or_insert(std::time::Duration::from_secs(30));
+
         // create service handler to serve
         let sockets = std::mem::take(&mut self.sockets);
         let mut servers = vec![];
@@ -341,7 +484,10 @@ impl Fibra {
             let con = Arc::new(Connection::from((server, client)));
 
             tokio::task::spawn(async move {
-                let _ = Builder::new(hyper_util::rt::TokioExecutor::new()).serve_connection(io, service_fn(|req: hyper::Request<hyper::body::Incoming>| {
+                let mut builder = Builder::new(hyper_util::rt::TokioExecutor::new());
+                builder.http2().enable_connect_protocol();
+
+                let _ = builder.serve_connection_with_upgrades(io, service_fn(|req: hyper::Request<hyper::body::Incoming>| {
                     // construct our own context object for each request
                     let ctx = Context::new(app.clone(), con.clone(), Request::from(req));
 
@@ -352,6 +498,9 @@ impl Fibra {
         };
 
         for socket in sockets {
+            // tokio requires non-blocking sockets
+            socket.set_nonblocking(true)?;
+
             let tcp = AsyncTcpListener::from_std(socket.into())?;
             let srv = {
                 let app = app.clone();
@@ -377,9 +526,31 @@ impl Fibra {
     }
 }
 
+impl Fibra {
+    /// Apply the body limits, subrouters can only tighten the limits of their parents, the
+    /// Content-Length is checked once a routine is selected
+    fn guard(&self, ctx: &mut Context) {
+        if self.body_limit.is_none() && self.read_timeout.is_none() {
+            return;
+        }
+
+        let mut body = std::mem::take(ctx.req_mut().body_mut());
+
+        if let Some(max) = self.body_limit {
+            body = body.limit(max);
+        }
+
+        if let Some(timeout) = self.read_timeout {
+            body = body.timeout(timeout);
+        }
+
+        *ctx.req_mut().body_mut() = body;
+    }
+}
+
 #[async_trait]
 impl Handler for Fibra {
-    async fn handle(&self, ctx: Context) -> FibraResult<Response> {
+    async fn handle(&self, mut ctx: Context) -> FibraResult<Response> {
         // match the beginning segment
         if !ctx.path().as_bytes().starts_with(self.initial.as_ref()) {
             return ctx.next().await;
@@ -387,18 +558,34 @@ impl Handler for Fibra {
 
         // block requests that fail the test
         if let Some(limiter) = &self.limiter {
-            if !limiter.test(&ctx) {
+            if let Err(status) = limiter.check(&ctx) {
+                if let Some(status) = status {
+                    ctx.rejected(status);
+                }
+
                 return ctx.next().await;
             }
         }
 
+        // keep the router in the stack so handlers can find its states
+        ctx.push(self, true, 0);
+
+        self.guard(&mut ctx);
+
+        let next = ctx.next();
+
         // the root router and subrouters with a Catcher will handle errors here
         if let Some(catcher) = &self.catcher {
-            return Ok(catcher.protect(self.mounted.handle(ctx)).await);
+            return Ok(catcher.protect(next).await);
         }
 
         // subrouters without a Catcher will handle requests here. If an error occurs, it will
         // propagate up to the nearest parent that has a Catcher to handle it.
-        self.mounted.handle(ctx).await
+        next.await
+    }
+
+    #[inline]
+    fn select(&self, idx: usize) -> Option<&dyn Handler> {
+        self.mounted.get(idx).map(|handler| handler.as_ref())
     }
 }
\ No newline at end of file
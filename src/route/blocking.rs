@@ -0,0 +1,89 @@
+//! Blocking Handler
+use crate::route::*;
+use crate::types::*;
+
+/// A handler wrapper for synchronous functions, the function runs on the blocking pool so
+/// CPU-heavy or blocking code won't stall the async workers
+pub struct Blocking<F> {
+    func: Arc<F>,
+    limit: Option<usize>,
+}
+
+/// The request body read by Blocking, private so it never clashes with the app's own values
+struct BlockingBody(Bytes);
+
+/// Wrap a synchronous function into a handler, the request body is read before calling the
+/// function and can be accessed by `ctx.blocking_body()`, bodies larger than the limit or the
+/// router's max_body_size are rejected with PAYLOAD_TOO_LARGE, the smaller one wins
+///
+/// # Examples
+///
+/// ```
+/// use fibra::*;
+///
+/// #[tokio::main]
+/// async fn main() -> FibraResult<()> {
+///     let mut app = Fibra::new();
+///     app.post("/upper", blocking(|ctx: &Context| {
+///         ctx.blocking_body().map(|body| body.to_ascii_uppercase()).unwrap_or_default()
+///     }).limit(16))?;
+///     app.catch(|res, err| res.body(err.map(|e| e.to_string()).unwrap_or_default()));
+///
+///     let app = std::sync::Arc::new(app);
+///     let run = |body: &'static str| Context::new(app.clone(), Default::default(), Request::new().method(Method::POST).uri("http://localip.cc/upper").body(body)).next();
+///
+///     assert_eq!(run("fibra").await?.body_all().await.unwrap_or_default(), "FIBRA");
+///
+///     let mut res = run("the quick brown fox").await?;
+///
+///     assert_eq!(res.status_ref(), &Status::PAYLOAD_TOO_LARGE);
+///     assert_eq!(res.body_all().await.unwrap_or_default(), "payload too large, limit 16 bytes");
+///
+///     Ok(())
+/// }
+/// ```
+#[inline]
+pub fn blocking<F, R>(func: F) -> Blocking<F>
+where
+    F: Fn(&Context) -> R + Send + Sync + 'static,
+    R: IntoResponse + Send + 'static,
+{
+    Blocking { func: Arc::new(func), limit: None }
+}
+
+impl<F> Blocking<F> {
+    /// Set the max body size in bytes, the router's max_body_size by default
+    #[inline]
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+}
+
+impl Context {
+    /// The request body read by the `blocking` wrapper, None outside of it
+    #[inline]
+    pub fn blocking_body(&self) -> Option<&Bytes> {
+        self.get::<BlockingBody>().map(|body| &body.0)
+    }
+}
+
+#[async_trait]
+impl<F, R> Handler for Blocking<F>
+where
+    F: Fn(&Context) -> R + Send + Sync + 'static,
+    R: IntoResponse + Send + 'static,
+{
+    async fn handle(&self, mut ctx: Context) -> FibraResult<Response> {
+        let limit = self.limit.unwrap_or(ctx.body_limit());
+        let body = ctx.req_mut().body_mut().read_limit(limit).await?;
+        ctx.insert(BlockingBody(body));
+
+        let func = self.func.clone();
+
+        match tokio::task::spawn_blocking(move || func(&ctx).into_response()).await {
+            Ok(res) => res,
+            Err(err) => Err(FibraError::PanicError(err.to_string().into())),
+        }
+    }
+}
\ No newline at end of file
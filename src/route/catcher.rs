@@ -45,7 +45,7 @@ impl Catcher {
             Ok(ret) => match ret {
                 Ok(res) if res.status_ref().is_success() => res,
                 Ok(res) => handler(res, None),
-                Err(err) => handler(Status::INTERNAL_SERVER_ERROR.into(), Some(err)),
+                Err(err) => handler(err.response(), Some(err)),
             }
             Err(err) => match err.downcast_ref::<&str>() {
                 Some(err) => handler(Status::INTERNAL_SERVER_ERROR.into(), Some(FibraError::PanicError(err.to_string().into()))),
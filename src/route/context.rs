@@ -23,6 +23,21 @@ pub struct Context {
     /// The query string of the Uri
     queries: OnceCell<IndexMap<String, String>>,
 
+    /// The query pairs of the Uri, repeated keys are kept
+    queries_all: OnceCell<Vec<(String, String)>>,
+
+    /// The cookies of the Cookie headers
+    cookies: OnceCell<IndexMap<String, String>>,
+
+    /// Typed storage shared by middlewares and handlers
+    storage: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+
+    /// The status to reply if no handler accepts the request, 404 by default
+    rejected: Option<Status>,
+
+    /// The negotiated order of sibling routines, keyed by the address of their list
+    ranked: Vec<(usize, Vec<usize>)>,
+
     /// Internal routing stack, handler is the parent, vector is whether it's a vector, index is the index of children
     routing: Vec<(*const dyn Handler, bool, usize)>, // (handler, index, vector)
 }
@@ -36,7 +51,7 @@ impl Context {
     pub fn new(app: Arc<Fibra>, conn: Arc<Connection>, req: Request) -> Self {
         let served = conn.count_add(1);
 
-        let mut myself = Self { app, conn, served, req, params: IndexMap::new(), queries: OnceCell::new(), routing: vec![] };
+        let mut myself = Self { app, conn, served, req, params: IndexMap::new(), queries: OnceCell::new(), queries_all: OnceCell::new(), cookies: OnceCell::new(), storage: HashMap::new(), rejected: None, ranked: vec![], routing: vec![] };
         myself.push(myself.app().as_ref(), false, 0);
         myself
     }
@@ -47,6 +62,38 @@ impl Context {
         &self.app
     }
 
+    /// Find a shared state from the innermost router outward
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let mut app = Fibra::new();
+    /// app.state("db pool");
+    ///
+    /// let ctx = Context::from(app);
+    ///
+    /// assert_eq!(ctx.state::<&str>(), Some(&"db pool"));
+    /// assert_eq!(ctx.state::<u32>(), None);
+    /// ```
+    #[inline]
+    pub fn state<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        self.routing.iter().rev()
+            .filter_map(|(obj, _, _)| unsafe { &**obj }.as_any().downcast_ref::<Fibra>())
+            .chain(std::iter::once(self.app.as_ref()))
+            .find_map(|app| app.state_ref::<T>())
+    }
+
+    /// The path prefix of the innermost router
+    #[inline]
+    pub(crate) fn prefix(&self) -> &[u8] {
+        self.routing.iter().rev()
+            .find_map(|(obj, _, _)| unsafe { &**obj }.as_any().downcast_ref::<Fibra>())
+            .unwrap_or(self.app.as_ref())
+            .prefix()
+    }
+
     /// Current connection, multiple requests may belong to one connection
     #[inline]
     pub fn conn(&self) -> &Arc<Connection> {
@@ -405,10 +452,211 @@ impl Context {
     #[inline]
     pub fn queries(&self) -> &IndexMap<String, String> {
         self.queries.get_or_init(|| {
+            self.query_pairs().iter().cloned().collect()
+        })
+    }
+
+    /// Request's query values of the same key
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let ctx = Context::from(Request::new().uri("http://localip.cc/?tag=a&page=1&tag=b"));
+    ///
+    /// assert_eq!(ctx.query("tag"), "b");
+    /// assert_eq!(ctx.queries_all("tag"), vec!["a", "b"]);
+    /// assert_eq!(ctx.queries_all("key"), Vec::<&str>::new());
+    /// ```
+    #[inline]
+    pub fn queries_all(&self, key: &str) -> Vec<&str> {
+        self.query_pairs().iter().filter(|(k, _)| k == key).map(|(_, v)| v.as_str()).collect()
+    }
+
+    /// Request's query pairs in order, repeated keys are kept
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let ctx = Context::from(Request::new().uri("http://localip.cc/?tag=a&tag=b"));
+    ///
+    /// assert_eq!(ctx.query_pairs(), &vec![("tag".to_string(), "a".to_string()), ("tag".to_string(), "b".to_string())]);
+    /// ```
+    #[inline]
+    pub fn query_pairs(&self) -> &Vec<(String, String)> {
+        self.queries_all.get_or_init(|| {
             form_urlencoded::parse(self.req.query().as_bytes()).into_owned().collect()
         })
     }
 
+    /// Decode the query string into T, repeated keys become sequences, bracketed keys like
+    /// filter[status] become nested structs, failures are rejected with BAD_REQUEST
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// #[derive(serde::Deserialize)]
+    /// struct Filter {
+    ///     status: String,
+    ///     label: Option<String>,
+    /// }
+    ///
+    /// #[derive(serde::Deserialize)]
+    /// struct Search {
+    ///     tag: Vec<String>,
+    ///     page: Option<u32>,
+    ///     filter: Filter,
+    /// }
+    ///
+    /// fn main() -> FibraResult<()> {
+    ///     let ctx = Context::from(Request::new().uri("http://localip.cc/?tag=a&tag[]=b&filter[status]=open"));
+    ///     let search = ctx.query_as::<Search>()?;
+    ///
+    ///     assert_eq!(search.tag, vec!["a", "b"]);
+    ///     assert_eq!(search.page, None);
+    ///     assert_eq!(search.filter.status, "open");
+    ///     assert_eq!(search.filter.label, None);
+    ///
+    ///     let ctx = Context::from(Request::new().uri("http://localip.cc/?tag=a&page=x&filter[status]=open"));
+    ///     let err = ctx.query_as::<Search>().err().map(|e| (e.status(), e.to_string()));
+    ///
+    ///     assert_eq!(err, Some((Status::BAD_REQUEST, "invalid query: page: invalid u32 value: \"x\"".to_string())));
+    ///
+    ///     let ctx = Context::from(Request::new().uri("http://localip.cc/?tag=a&filter[label]=bug"));
+    ///     let err = ctx.query_as::<Search>().err().map(|e| e.to_string());
+    ///
+    ///     assert_eq!(err, Some("invalid query: filter[status]: missing field".to_string()));
+    ///
+    ///     let ctx = Context::from(Request::new().uri("http://localip.cc/?tag=a&page[x]=1&filter[status]=open"));
+    ///     let err = ctx.query_as::<Search>().err().map(|e| e.to_string());
+    ///
+    ///     assert_eq!(err, Some("invalid query: page: expected a value, found a map".to_string()));
+    ///
+    ///     let uri = format!("http://localip.cc/?filter{}=1", "[a]".repeat(40));
+    ///     let ctx = Context::from(Request::new().uri(Uri::try_from(uri)?));
+    ///     let err = ctx.query_as::<Search>().err().map(|e| (e.status(), e.to_string()));
+    ///
+    ///     assert_eq!(err, Some((Status::BAD_REQUEST, "invalid query: filter: key nested deeper than 32 levels".to_string())));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "serde")]
+    #[inline]
+    pub fn query_as<T: serde::de::DeserializeOwned>(&self) -> FibraResult<T> {
+        let pairs = self.query_pairs().iter().map(|(k, v)| (Cow::from(k.as_str()), Cow::from(v.as_str())));
+        Ok(decode_pairs(pairs).map_err(|err| Rejection::InvalidQuery(err.to_string()))?)
+    }
+
+    /// Request's cookie value
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let ctx = Context::from(Request::new().header(header::COOKIE, "uid=12345; theme=dark"));
+    ///
+    /// assert_eq!(ctx.cookie("uid"), Some("12345"));
+    /// assert_eq!(ctx.cookie("lang"), None);
+    /// ```
+    #[inline]
+    pub fn cookie(&self, name: &str) -> Option<&str> {
+        self.cookies().get(name).map(|v| v.as_str())
+    }
+
+    /// Request's cookies, all Cookie headers are parsed on first access
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let mut req = Request::new().header(header::COOKIE, "uid=12345; theme=dark");
+    /// req.headers_mut().append(header::COOKIE, HeaderValue::from_static("lang=en"));
+    ///
+    /// let ctx = Context::from(req);
+    ///
+    /// assert_eq!(ctx.cookies(), &indexmap::indexmap! { "uid".to_string() => "12345".to_string(), "theme".to_string() => "dark".to_string(), "lang".to_string() => "en".to_string() });
+    /// ```
+    #[inline]
+    pub fn cookies(&self) -> &IndexMap<String, String> {
+        self.cookies.get_or_init(|| {
+            self.req.headers_ref().get_all(header::COOKIE).iter()
+                .filter_map(|v| v.to_str().ok())
+                .flat_map(Cookie::split_parse)
+                .filter_map(Result::ok)
+                .map(|c| (c.name().to_string(), c.value().to_string()))
+                .collect()
+        })
+    }
+
+    /// Request's signed cookie value, the Key is found in the states like Context::state, None
+    /// if the key is absent or the signature is invalid
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let key = Key::generate();
+    /// let res = Response::new().signed_cookie(Cookie::new("uid", "12345"), &key);
+    /// let signed = res.header_ref(header::SET_COOKIE).and_then(|v| v.to_str().ok()).unwrap_or("").to_string();
+    ///
+    /// let mut app = Fibra::new();
+    /// app.state(key);
+    ///
+    /// let ctx = Context::from((app, Request::new().header(header::COOKIE, HeaderValue::try_from(signed).unwrap_or(HeaderValue::from_static("")))));
+    ///
+    /// assert_eq!(ctx.signed_cookie("uid"), Some("12345".to_string()));
+    ///
+    /// let mut app = Fibra::new();
+    /// app.state(Key::generate());
+    ///
+    /// let ctx = Context::from((app, Request::new().header(header::COOKIE, "uid=tampered")));
+    ///
+    /// assert_eq!(ctx.signed_cookie("uid"), None);
+    /// ```
+    pub fn signed_cookie(&self, name: &str) -> Option<String> {
+        let key = self.state::<Key>()?;
+        let mut jar = CookieJar::new();
+        jar.add_original(Cookie::new(name.to_string(), self.cookie(name)?.to_string()));
+        jar.signed(key).get(name).map(|c| c.value().to_string())
+    }
+
+    /// Request's encrypted cookie value, the Key is found in the states like Context::state,
+    /// None if the key is absent or the value can't be decrypted
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let key = Key::generate();
+    /// let res = Response::new().private_cookie(Cookie::new("token", "secret"), &key);
+    /// let sealed = res.header_ref(header::SET_COOKIE).and_then(|v| v.to_str().ok()).unwrap_or("").to_string();
+    ///
+    /// assert_eq!(sealed.contains("secret"), false);
+    ///
+    /// let mut app = Fibra::new();
+    /// app.state(key);
+    ///
+    /// let ctx = Context::from((app, Request::new().header(header::COOKIE, HeaderValue::try_from(sealed).unwrap_or(HeaderValue::from_static("")))));
+    ///
+    /// assert_eq!(ctx.private_cookie("token"), Some("secret".to_string()));
+    /// ```
+    pub fn private_cookie(&self, name: &str) -> Option<String> {
+        let key = self.state::<Key>()?;
+        let mut jar = CookieJar::new();
+        jar.add_original(Cookie::new(name.to_string(), self.cookie(name)?.to_string()));
+        jar.private(key).get(name).map(|c| c.value().to_string())
+    }
+
     /// Request's whole uri
     ///
     /// # Examples
@@ -469,6 +717,40 @@ impl Context {
         self.version() == &Version::HTTP_2
     }
 
+    /// Request's content type
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let ctx = Context::from(Request::new().header(header::CONTENT_TYPE, "application/json"));
+    ///
+    /// assert_eq!(ctx.content_type(), Some(mime::APPLICATION_JSON));
+    /// ```
+    #[inline]
+    pub fn content_type(&self) -> Option<Mime> {
+        self.req.content_type()
+    }
+
+    /// Check if a mime type is acceptable to the client
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let ctx = Context::from(Request::new().header(header::ACCEPT, "text/html, application/*;q=0.5"));
+    ///
+    /// assert_eq!(ctx.accepts(&mime::TEXT_HTML), true);
+    /// assert_eq!(ctx.accepts(&mime::APPLICATION_JSON), true);
+    /// assert_eq!(ctx.accepts(&mime::IMAGE_PNG), false);
+    /// ```
+    #[inline]
+    pub fn accepts(&self, mime: &Mime) -> bool {
+        self.req.accepts(mime)
+    }
+
     /// Request's header value
     ///
     /// # Examples
@@ -487,6 +769,21 @@ impl Context {
         self.req.header_ref(key)
     }
 
+    /// The id of the last received event sent by reconnecting Server-Sent Events clients
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// assert_eq!(Context::from(Request::new().header("last-event-id", "42")).last_event_id(), Some("42"));
+    /// assert_eq!(Context::default().last_event_id(), None);
+    /// ```
+    #[inline]
+    pub fn last_event_id(&self) -> Option<&str> {
+        self.header("last-event-id").and_then(|v| v.to_str().ok())
+    }
+
     /// Request's headers
     ///
     /// # Examples
@@ -544,6 +841,83 @@ impl Context {
     }
 }
 
+impl Context {
+    /// Store a typed value, return the old one if exists
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// struct User(&'static str);
+    ///
+    /// let mut ctx = Context::default();
+    ///
+    /// assert_eq!(ctx.insert(User("alice")), None);
+    /// assert_eq!(ctx.insert(User("bob")), Some(User("alice")));
+    /// assert_eq!(ctx.get::<User>(), Some(&User("bob")));
+    /// ```
+    #[inline]
+    pub fn insert<T: Send + Sync + 'static>(&mut self, val: T) -> Option<T> {
+        self.storage.insert(TypeId::of::<T>(), Box::new(val)).and_then(|old| old.downcast().ok().map(|old| *old))
+    }
+
+    /// Get a typed value
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let mut ctx = Context::default();
+    /// ctx.insert(12345u32);
+    ///
+    /// assert_eq!(ctx.get::<u32>(), Some(&12345));
+    /// assert_eq!(ctx.get::<u64>(), None);
+    /// ```
+    #[inline]
+    pub fn get<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        self.storage.get(&TypeId::of::<T>()).and_then(|val| val.downcast_ref())
+    }
+
+    /// Get/Set a typed value
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let mut ctx = Context::default();
+    /// ctx.insert(vec![1, 2]);
+    /// ctx.get_mut::<Vec<i32>>().map(|v| v.push(3));
+    ///
+    /// assert_eq!(ctx.get::<Vec<i32>>(), Some(&vec![1, 2, 3]));
+    /// ```
+    #[inline]
+    pub fn get_mut<T: Send + Sync + 'static>(&mut self) -> Option<&mut T> {
+        self.storage.get_mut(&TypeId::of::<T>()).and_then(|val| val.downcast_mut())
+    }
+
+    /// Remove a typed value
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let mut ctx = Context::default();
+    /// ctx.insert("token".to_string());
+    ///
+    /// assert_eq!(ctx.remove::<String>(), Some("token".to_string()));
+    /// assert_eq!(ctx.remove::<String>(), None);
+    /// ```
+    #[inline]
+    pub fn remove<T: Send + Sync + 'static>(&mut self) -> Option<T> {
+        self.storage.remove(&TypeId::of::<T>()).and_then(|val| val.downcast().ok().map(|val| *val))
+    }
+}
+
 impl Context {
     /// Read all body contents into a BufList
     #[inline]
@@ -551,11 +925,370 @@ impl Context {
         self.req.body_mut().read_all().await
     }
 
+    /// Request's trailers, they are only available after the body is consumed
+    #[inline]
+    pub fn trailers(&self) -> Option<&HeaderMap> {
+        self.req.trailers()
+    }
+
+    /// Read all bytes, errors are returned instead of a partial body
+    #[inline]
+    pub async fn try_read_all(&mut self) -> FibraResult<Bytes> {
+        self.req.body_mut().try_read_all().await
+    }
+
+    /// Read one frame into a Bytes, errors are returned instead of the end of the body
+    #[inline]
+    pub async fn try_read_frame(&mut self) -> FibraResult<Option<Bytes>> {
+        self.req.body_mut().try_read_frame().await
+    }
+
+    /// Read all bytes, bodies larger than max are rejected with PAYLOAD_TOO_LARGE
+    #[inline]
+    pub async fn read_limit(&mut self, max: usize) -> FibraResult<Bytes> {
+        self.req.body_mut().read_limit(max).await
+    }
+
+    /// The size limit of the body, the router's max_body_size or 16 MiB if it's not set
+    #[inline]
+    pub(crate) fn body_limit(&self) -> usize {
+        self.req.body_ref().limit_ref().unwrap_or(BODY_LIMIT)
+    }
+
     /// Read one frame into a Bytes
     #[inline]
     pub async fn read_frame(&mut self) -> Option<Bytes> {
         self.req.body_mut().read_frame().await
     }
+
+    /// Decode the json body into T within the router's max_body_size, 16 MiB if it's not set,
+    /// see json_limit
+    #[cfg(feature = "serde")]
+    #[inline]
+    pub async fn json<T: serde::de::DeserializeOwned>(&mut self) -> FibraResult<T> {
+        self.json_limit(self.body_limit()).await
+    }
+
+    /// Decode the json body into T, the content type must be application/json or end with
+    /// +json, otherwise it's rejected with UNSUPPORTED_MEDIA_TYPE, bodies larger than max or the
+    /// router's max_body_size are rejected with PAYLOAD_TOO_LARGE, the smaller one wins,
+    /// malformed bodies are rejected with BAD_REQUEST along with the json path of the failure
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// #[derive(serde::Deserialize)]
+    /// struct User {
+    ///     name: String,
+    ///     tags: Vec<u32>,
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> FibraResult<()> {
+    ///     let json = |body: &'static str| Context::from(Request::new().header(header::CONTENT_TYPE, "application/json").body(body));
+    ///
+    ///     let user = json(r#"{"name":"fibra","tags":[1,2]}"#).json_limit::<User>(64).await?;
+    ///
+    ///     assert_eq!((user.name.as_str(), user.tags), ("fibra", vec![1, 2]));
+    ///
+    ///     let err = json(r#"{"name":"fibra","tags":[1,"2"]}"#).json_limit::<User>(64).await.err();
+    ///
+    ///     assert_eq!(err.as_ref().map(|e| e.status()), Some(Status::BAD_REQUEST));
+    ///     assert_eq!(err.as_ref().map(|e| e.to_string()).unwrap_or_default(), "invalid json at tags[1]: invalid type: string \"2\", expected u32 at line 1 column 29");
+    ///
+    ///     let err = json(r#"{"name":"fibra","tags":[1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17,18,19,20,21,22,23]}"#).json_limit::<User>(64).await.err();
+    ///
+    ///     assert_eq!(err.map(|e| e.status()), Some(Status::PAYLOAD_TOO_LARGE));
+    ///
+    ///     let mut ctx = Context::from(Request::new().header(header::CONTENT_TYPE, "text/plain").body("{}"));
+    ///
+    ///     assert_eq!(ctx.json_limit::<User>(64).await.err().map(|e| e.status()), Some(Status::UNSUPPORTED_MEDIA_TYPE));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "serde")]
+    pub async fn json_limit<T: serde::de::DeserializeOwned>(&mut self, max: usize) -> FibraResult<T> {
+        let json = self.content_type().map(|mime| mime.subtype() == mime::JSON || mime.suffix() == Some(mime::JSON)).unwrap_or(false);
+
+        if !json {
+            return Err(Rejection::UnsupportedMediaType(mime::APPLICATION_JSON).into());
+        }
+
+        let body = self.req.body_mut().read_limit(max).await?;
+        let mut de = serde_json::Deserializer::from_slice(&body);
+
+        serde_path_to_error::deserialize(&mut de).map_err(|err| {
+            Rejection::InvalidJson { path: err.path().to_string(), message: err.inner().to_string() }.into()
+        })
+    }
+
+    /// Decode the form body into a multi-value map within the router's max_body_size, 16 MiB if
+    /// it's not set, see form_limit
+    #[inline]
+    pub async fn form(&mut self) -> FibraResult<IndexMap<String, Vec<String>>> {
+        self.form_limit(self.body_limit()).await
+    }
+
+    /// Decode the form body into a multi-value map, the content type must be
+    /// application/x-www-form-urlencoded with a utf-8, iso-8859-1 or us-ascii charset, otherwise
+    /// it's rejected with UNSUPPORTED_MEDIA_TYPE, bodies larger than max or the router's
+    /// max_body_size are rejected with PAYLOAD_TOO_LARGE, the smaller one wins
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> FibraResult<()> {
+    ///     let form = |mime: &'static str, body: &'static [u8]| Context::from(Request::new().header(header::CONTENT_TYPE, mime).body(body));
+    ///
+    ///     let map = form("application/x-www-form-urlencoded", b"tag=a&name=%E4%BD%A0%E5%A5%BD&tag=b").form_limit(64).await?;
+    ///
+    ///     assert_eq!(map["tag"], vec!["a", "b"]);
+    ///     assert_eq!(map["name"], vec!["你好"]);
+    ///
+    ///     let map = form("application/x-www-form-urlencoded; charset=iso-8859-1", b"name=caf%E9+au+lait").form_limit(64).await?;
+    ///
+    ///     assert_eq!(map["name"], vec!["café au lait"]);
+    ///
+    ///     assert_eq!(form("application/x-www-form-urlencoded", b"name=fibra").form_limit(4).await.err().map(|e| e.status()), Some(Status::PAYLOAD_TOO_LARGE));
+    ///     assert_eq!(form("application/x-www-form-urlencoded; charset=gbk", b"name=fibra").form_limit(64).await.err().map(|e| e.status()), Some(Status::UNSUPPORTED_MEDIA_TYPE));
+    ///     assert_eq!(form("application/json", b"{}").form_limit(64).await.err().map(|e| e.status()), Some(Status::UNSUPPORTED_MEDIA_TYPE));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn form_limit(&mut self, max: usize) -> FibraResult<IndexMap<String, Vec<String>>> {
+        let mut map: IndexMap<String, Vec<String>> = IndexMap::new();
+
+        for (key, val) in self.form_pairs(max).await? {
+            map.entry(key).or_default().push(val);
+        }
+
+        Ok(map)
+    }
+
+    /// Decode the form body into T within the router's max_body_size, 16 MiB if it's not set,
+    /// see form_as_limit
+    #[cfg(feature = "serde")]
+    #[inline]
+    pub async fn form_as<T: serde::de::DeserializeOwned>(&mut self) -> FibraResult<T> {
+        self.form_as_limit(self.body_limit()).await
+    }
+
+    /// Decode the form body into T, repeated keys become sequences, bracketed keys like
+    /// user[name] become nested structs, failures are rejected with BAD_REQUEST
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// #[derive(serde::Deserialize)]
+    /// struct Login {
+    ///     user: String,
+    ///     remember: Option<bool>,
+    ///     roles: Vec<String>,
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> FibraResult<()> {
+    ///     let form = |body: &'static str| Context::from(Request::new().header(header::CONTENT_TYPE, "application/x-www-form-urlencoded").body(body));
+    ///     let login = form("user=fibra&roles[]=admin&roles[]=dev").form_as_limit::<Login>(64).await?;
+    ///
+    ///     assert_eq!((login.user.as_str(), login.remember, login.roles), ("fibra", None, vec!["admin".to_string(), "dev".to_string()]));
+    ///
+    ///     let err = form("user=fibra&remember=maybe&roles=dev").form_as_limit::<Login>(64).await.err();
+    ///
+    ///     assert_eq!(err.map(|e| e.to_string()).unwrap_or_default(), "invalid form: remember: invalid bool value: \"maybe\"");
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "serde")]
+    pub async fn form_as_limit<T: serde::de::DeserializeOwned>(&mut self, max: usize) -> FibraResult<T> {
+        let pairs = self.form_pairs(max).await?;
+        Ok(decode_pairs(pairs.into_iter().map(|(k, v)| (Cow::from(k), Cow::from(v)))).map_err(|err| Rejection::InvalidForm(err.to_string()))?)
+    }
+
+    /// Parse the multipart body with default limits, see multipart_limit
+    #[inline]
+    pub fn multipart(&mut self) -> FibraResult<Multipart> {
+        self.multipart_limit(MultipartLimit::new())
+    }
+
+    /// Parse the multipart body, the content type must be multipart/form-data with a boundary,
+    /// otherwise it's rejected with UNSUPPORTED_MEDIA_TYPE, the body is taken from the context
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> FibraResult<()> {
+    ///     let body = "--X\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\n\r\nfile content\r\n--X--\r\n";
+    ///     let mut ctx = Context::from(Request::new().header(header::CONTENT_TYPE, "multipart/form-data; boundary=X").body(body));
+    ///     let mut form = ctx.multipart_limit(MultipartLimit::new().parts(10).field(1024).total(4096))?;
+    ///
+    ///     while let Some(field) = form.next_field().await? {
+    ///         assert_eq!((field.name(), field.file_name()), (Some("file"), Some("a.txt")));
+    ///         assert_eq!(field.bytes().await?, "file content");
+    ///     }
+    ///
+    ///     let mut ctx = Context::from(Request::new().header(header::CONTENT_TYPE, "multipart/form-data").body(body));
+    ///
+    ///     assert_eq!(ctx.multipart().err().map(|e| e.status()), Some(Status::BAD_REQUEST));
+    ///     assert_eq!(Context::default().multipart().err().map(|e| e.status()), Some(Status::UNSUPPORTED_MEDIA_TYPE));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn multipart_limit(&mut self, limit: MultipartLimit) -> FibraResult<Multipart> {
+        let mime = match self.content_type() {
+            Some(mime) if mime.essence_str() == mime::MULTIPART_FORM_DATA.essence_str() => mime,
+            _ => return Err(Rejection::UnsupportedMediaType(mime::MULTIPART_FORM_DATA).into()),
+        };
+
+        let boundary = match mime.get_param(mime::BOUNDARY) {
+            Some(boundary) => boundary.as_str().to_string(),
+            None => return Err(Rejection::InvalidMultipart("missing boundary".to_string()).into()),
+        };
+
+        Ok(Multipart::new(std::mem::take(self.req.body_mut()), boundary, limit))
+    }
+
+    /// Validate the websocket handshake, then reply the returned object's `on_upgrade` to take
+    /// over the connection, invalid handshakes are rejected with BAD_REQUEST or UPGRADE_REQUIRED
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let mut ctx = Context::from(Request::new().uri("http://localip.cc/ws").version(Version::HTTP_11));
+    ///
+    /// assert_eq!(ctx.upgrade_websocket().err().map(|e| e.status()), Some(Status::BAD_REQUEST));
+    ///
+    /// let mut ctx = Context::from(Request::new().uri("http://localip.cc/ws").version(Version::HTTP_11)
+    ///     .header(header::CONNECTION, "Upgrade")
+    ///     .header(header::UPGRADE, "websocket")
+    ///     .header(header::SEC_WEBSOCKET_KEY, "dGhlIHNhbXBsZSBub25jZQ==")
+    ///     .header(header::SEC_WEBSOCKET_VERSION, "8"));
+    ///
+    /// assert_eq!(ctx.upgrade_websocket().err().map(|e| e.status()), Some(Status::UPGRADE_REQUIRED));
+    /// ```
+    #[inline]
+    pub fn upgrade_websocket(&mut self) -> FibraResult<WebSocketUpgrade> {
+        WebSocketUpgrade::new(&mut self.req)
+    }
+
+    /// Take over the connection after the response is sent, reply 101 with an Upgrade header for
+    /// HTTP/1.1 upgrades or 2xx for CONNECT requests, then await the future to get the raw io,
+    /// requests that can't be upgraded are rejected with BAD_REQUEST
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    /// use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> FibraResult<()> {
+    ///     // the target echoes what it receives
+    ///     let target = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    ///     let target_addr = target.local_addr()?;
+    ///
+    ///     tokio::spawn(async move {
+    ///         while let Ok((mut stream, _)) = target.accept().await {
+    ///             tokio::spawn(async move {
+    ///                 let (mut reader, mut writer) = stream.split();
+    ///                 let _ = tokio::io::copy(&mut reader, &mut writer).await;
+    ///             });
+    ///         }
+    ///     });
+    ///
+    ///     // the proxy tunnels CONNECT requests to their authority
+    ///     let mut app = Fibra::new();
+    ///     app.mount(|mut ctx: Context| async move {
+    ///         if !ctx.is_connect() {
+    ///             return ctx.next().await;
+    ///         }
+    ///
+    ///         let mut remote = tokio::net::TcpStream::connect(ctx.authority().map(|v| v.to_string()).unwrap_or_default()).await?;
+    ///         let upgrade = ctx.on_upgrade()?;
+    ///
+    ///         tokio::spawn(async move {
+    ///             if let Ok(mut io) = upgrade.await {
+    ///                 let _ = tokio::io::copy_bidirectional(&mut io, &mut remote).await;
+    ///             }
+    ///         });
+    ///
+    ///         Ok(Response::new())
+    ///     });
+    ///
+    ///     let proxy_addr = app.bind("127.0.0.1:0")?.local_addr()?.as_socket().ok_or(FibraError::AddrNotAvailable)?;
+    ///     tokio::spawn(app.run());
+    ///
+    ///     let mut stream = tokio::net::TcpStream::connect(proxy_addr).await?;
+    ///     stream.write_all(format!("CONNECT {0} HTTP/1.1\r\nHost: {0}\r\n\r\n", target_addr).as_bytes()).await?;
+    ///
+    ///     let mut head = vec![];
+    ///     while !head.ends_with(b"\r\n\r\n") {
+    ///         head.push(stream.read_u8().await?);
+    ///     }
+    ///
+    ///     assert_eq!(head.starts_with(b"HTTP/1.1 200"), true);
+    ///
+    ///     let mut echo = [0u8; 12];
+    ///     stream.write_all(b"Hello World!").await?;
+    ///     stream.read_exact(&mut echo).await?;
+    ///
+    ///     assert_eq!(&echo, b"Hello World!");
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[inline]
+    pub fn on_upgrade(&mut self) -> FibraResult<impl Future<Output = FibraResult<Upgraded>> + Send + 'static> {
+        let upgrade = match self.req.extensions_mut().remove::<hyper::upgrade::OnUpgrade>() {
+            Some(upgrade) => upgrade,
+            None => return Err(Rejection::InvalidUpgrade("connection is not upgradable".to_string()).into()),
+        };
+
+        Ok(async move { Ok(TokioIo::new(upgrade.await?)) })
+    }
+
+    /// Read the form body and decode it by the charset
+    async fn form_pairs(&mut self, max: usize) -> FibraResult<Vec<(String, String)>> {
+        let expect = || Rejection::UnsupportedMediaType(mime::APPLICATION_WWW_FORM_URLENCODED);
+        let mime = self.content_type().ok_or_else(expect)?;
+
+        if mime.essence_str() != mime::APPLICATION_WWW_FORM_URLENCODED.essence_str() {
+            return Err(expect().into());
+        }
+
+        let latin1 = match mime.get_param(mime::CHARSET).map(|v| v.as_str().to_ascii_lowercase()) {
+            None => false,
+            Some(charset) if charset == "utf-8" || charset == "utf8" => false,
+            Some(charset) if charset == "iso-8859-1" || charset == "latin1" || charset == "us-ascii" => true,
+            Some(_) => return Err(expect().into()),
+        };
+
+        let body = self.req.body_mut().read_limit(max).await?;
+
+        Ok(match latin1 {
+            false => form_urlencoded::parse(&body).into_owned().collect(),
+            true => body.split(|b| *b == b'&').filter(|pair| !pair.is_empty()).map(|pair| {
+                let mut iter = pair.splitn(2, |b| *b == b'=');
+                (decode_latin1(iter.next().unwrap_or_default()), decode_latin1(iter.next().unwrap_or_default()))
+            }).collect(),
+        })
+    }
 }
 
 impl Context {
@@ -579,7 +1312,8 @@ impl Context {
         Ok(status.unwrap_or(Status::FORBIDDEN).into())
     }
 
-    /// Rewrite the current request server-side without the client perceiving it
+    /// Rewrite the current request server-side without the client perceiving it, the typed
+    /// storage is kept
     ///
     /// # Examples
     ///
@@ -590,9 +1324,10 @@ impl Context {
     /// async fn main() -> FibraResult<()> {
     ///     let app = fibra! { 
     ///         get("/v1") => "v1",
-    ///         get("/v2") => "v2",
+    ///         get("/v2") => |ctx: Context| async move { ctx.get::<&'static str>().copied().unwrap_or("") },
     ///     };
-    ///     let ctx = Context::from((app, Request::new().uri("http://localip.cc/v1")));
+    ///     let mut ctx = Context::from((app, Request::new().uri("http://localip.cc/v1")));
+    ///     ctx.insert("v2");
     ///
     ///     assert_eq!(ctx.rewrite("http://localip.cc/v2", None).await?.body_all().await.unwrap_or_default(), "v2");
     ///
@@ -606,7 +1341,8 @@ impl Context {
             None => std::mem::take(self.req.body_mut()),
         };
 
-        let ctx = Context::new(self.app, self.conn, self.req.uri(Uri::try_from(to.as_ref())?).body(body));
+        let mut ctx = Context::new(self.app, self.conn, self.req.uri(Uri::try_from(to.as_ref())?).body(body));
+        ctx.storage = self.storage;
         ctx.next().await
     }
 
@@ -632,6 +1368,56 @@ impl Context {
         Ok(Response::new().status(code).header(header::LOCATION, location))
     }
 
+    /// Reply a file with the request's conditional and range headers honored
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> FibraResult<()> {
+    ///     let path = std::env::temp_dir().join("fibra-ctx-file.txt");
+    ///     std::fs::write(&path, "Hello World!")?;
+    ///
+    ///     let ctx = Context::from(Request::new().header(header::RANGE, "bytes=6-"));
+    ///     let mut res = ctx.file(&path).await?;
+    ///
+    ///     assert_eq!(res.status_ref(), &Status::PARTIAL_CONTENT);
+    ///     assert_eq!(res.body_all().await.unwrap_or_default(), "World!");
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[inline]
+    pub async fn file(&self, path: impl AsRef<std::path::Path>) -> FibraResult<Response> {
+        Response::new().file_for(path, &self.req).await
+    }
+
+    /// Set the status to reply if no handler accepts the request, the first one wins
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> FibraResult<()> {
+    ///     let mut ctx = Context::default();
+    ///     ctx.rejected(Status::NOT_ACCEPTABLE);
+    ///     ctx.rejected(Status::UNSUPPORTED_MEDIA_TYPE);
+    ///
+    ///     assert_eq!(ctx.next().await?.status_ref(), &Status::NOT_ACCEPTABLE);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[inline]
+    pub fn rejected(&mut self, status: Status) -> &mut Self {
+        self.rejected.get_or_insert(status);
+        self
+    }
+
     /// Find the next handler and execute it
     pub async fn next(mut self) -> FibraResult<Response> {
         while let Some((obj, vec, idx)) = self.routing.last_mut() {
@@ -643,16 +1429,29 @@ impl Context {
                 return cur.handle(self).await;
             }
 
-            // child handler
-            if let Some(cld) = cur.select(*idx) {
+            // child handler, in the negotiated order if any
+            let addr = *obj as *const () as usize;
+            let pos = self.ranked.iter().find(|(set, _)| *set == addr).and_then(|(_, order)| order.get(*idx).copied()).unwrap_or(*idx);
+
+            if let Some(cld) = cur.select(pos) {
                 *idx += 1;
                 return cld.handle(self).await;
             }
 
+            self.ranked.retain(|(set, _)| *set != addr);
             self.routing.pop();
         }
 
-        Ok(Status::NOT_FOUND.into())
+        Ok(self.rejected.unwrap_or(Status::NOT_FOUND).into())
+    }
+
+    /// Visit the children of a group in the given order, the order is dropped when the group
+    /// is exhausted
+    #[inline]
+    pub(crate) fn rank(&mut self, set: *const dyn Handler, order: Vec<usize>) {
+        let set = set as *const () as usize;
+        self.ranked.retain(|(addr, _)| *addr != set);
+        self.ranked.push((set, order));
     }
 
     /// Push the nested group of handlers into stack
@@ -716,4 +1515,27 @@ impl From<Request> for Context {
     fn from(req: Request) -> Self {
         Self::new(Arc::new(Fibra::new()), Arc::new(Connection::new()), req)
     }
+}
+
+/// Decode a percent-encoded latin1 string, each byte maps to the same code point
+fn decode_latin1(raw: &[u8]) -> String {
+    let hex = |b: Option<&u8>| b.and_then(|b| (*b as char).to_digit(16)).map(|d| d as u8);
+    let mut out = String::with_capacity(raw.len());
+    let mut idx = 0;
+
+    while idx < raw.len() {
+        let byte = match (raw[idx], hex(raw.get(idx + 1)), hex(raw.get(idx + 2))) {
+            (b'+', _, _) => b' ',
+            (b'%', Some(high), Some(low)) => {
+                idx += 2;
+                high << 4 | low
+            }
+            (byte, _, _) => byte,
+        };
+
+        out.push(byte as char);
+        idx += 1;
+    }
+
+    out
 }
\ No newline at end of file
@@ -0,0 +1,487 @@
+//! Extract Request Data
+use crate::route::*;
+use crate::types::*;
+
+/// Types that can be extracted from the context, extraction failures are returned as errors
+/// and handled by the nearest Catcher
+#[async_trait]
+pub trait FromContext: Sized + Send + 'static {
+    /// Extract the value, the context may be modified, e.g. the body is taken
+    async fn from_context(ctx: &mut Context) -> FibraResult<Self>;
+}
+
+/// Optional extraction, failures become None
+///
+/// # Examples
+///
+/// ```
+/// use fibra::*;
+///
+/// #[tokio::main]
+/// async fn main() -> FibraResult<()> {
+///     let mut ctx = Context::default();
+///
+///     assert_eq!(Option::<Header<UserAgent>>::from_context(&mut ctx).await?.is_none(), true);
+///
+///     Ok(())
+/// }
+/// ```
+#[async_trait]
+impl<T: FromContext> FromContext for Option<T> {
+    #[inline]
+    async fn from_context(ctx: &mut Context) -> FibraResult<Self> {
+        Ok(T::from_context(ctx).await.ok())
+    }
+}
+
+/// Named params after matching
+///
+/// # Examples
+///
+/// ```
+/// use fibra::*;
+///
+/// #[derive(serde::Deserialize)]
+/// struct User {
+///     id: u32,
+///     name: String,
+/// }
+///
+/// #[tokio::main]
+/// async fn main() -> FibraResult<()> {
+///     let mut ctx = Context::default();
+///     ctx.params_mut().insert("id".to_string(), "12345".to_string());
+///
+///     assert_eq!(Param::<u32>::from_context(&mut ctx).await?.0, 12345);
+///     assert_eq!(Param::<String>::from_context(&mut ctx).await?.0, "12345");
+///     assert_eq!(Param::<User>::from_context(&mut ctx).await.is_err(), true);
+///
+///     ctx.params_mut().insert("name".to_string(), "fibra".to_string());
+///
+///     let Param(user) = Param::<User>::from_context(&mut ctx).await?;
+///
+///     assert_eq!((user.id, user.name.as_str()), (12345, "fibra"));
+///
+///     Ok(())
+/// }
+/// ```
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone)]
+pub struct Param<T>(pub T);
+
+#[cfg(feature = "serde")]
+#[async_trait]
+impl<T: serde::de::DeserializeOwned + Send + 'static> FromContext for Param<T> {
+    #[inline]
+    async fn from_context(ctx: &mut Context) -> FibraResult<Self> {
+        let pairs = ctx.params().iter().map(|(k, v)| (Cow::from(k.as_str()), Cow::from(v.as_str())));
+
+        // a single param can also be extracted as a plain value
+        let val = match (decode_pairs(pairs), ctx.params().values().next()) {
+            (Err(_), Some(val)) if ctx.params().len() == 1 => decode_value(val),
+            (val, _) => val,
+        };
+
+        Ok(Self(val.map_err(|err| Rejection::InvalidParam(err.to_string()))?))
+    }
+}
+
+/// Query string, repeated keys can be decoded as sequences
+///
+/// # Examples
+///
+/// ```
+/// use fibra::*;
+///
+/// #[derive(serde::Deserialize)]
+/// struct Search {
+///     q: String,
+///     page: Option<u32>,
+///     tag: Vec<String>,
+/// }
+///
+/// #[tokio::main]
+/// async fn main() -> FibraResult<()> {
+///     let mut ctx = Context::from(Request::new().uri("http://localip.cc/?q=fibra&tag=a&tag=b"));
+///     let Query(search) = Query::<Search>::from_context(&mut ctx).await?;
+///
+///     assert_eq!(search.q, "fibra");
+///     assert_eq!(search.page, None);
+///     assert_eq!(search.tag, vec!["a", "b"]);
+///
+///     let mut ctx = Context::from(Request::new().uri("http://localip.cc/?q=fibra&tag=a&page=x"));
+///
+///     assert_eq!(Query::<Search>::from_context(&mut ctx).await.map_err(|err| err.status()).err(), Some(Status::BAD_REQUEST));
+///
+///     Ok(())
+/// }
+/// ```
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone)]
+pub struct Query<T>(pub T);
+
+#[cfg(feature = "serde")]
+#[async_trait]
+impl<T: serde::de::DeserializeOwned + Send + 'static> FromContext for Query<T> {
+    #[inline]
+    async fn from_context(ctx: &mut Context) -> FibraResult<Self> {
+        ctx.query_as().map(Self)
+    }
+}
+
+/// Json body, the content type must be json, see Context::json
+///
+/// # Examples
+///
+/// ```
+/// use fibra::*;
+///
+/// #[derive(serde::Deserialize)]
+/// struct User {
+///     name: String,
+/// }
+///
+/// #[tokio::main]
+/// async fn main() -> FibraResult<()> {
+///     let mut ctx = Context::from(Request::new().header(header::CONTENT_TYPE, "application/json").body(r#"{"name":"fibra"}"#));
+///
+///     assert_eq!(Json::<User>::from_context(&mut ctx).await?.0.name, "fibra");
+///
+///     let mut ctx = Context::from(Request::new().body(r#"{"name":"fibra"}"#));
+///
+///     assert_eq!(Json::<User>::from_context(&mut ctx).await.map_err(|err| err.status()).err(), Some(Status::UNSUPPORTED_MEDIA_TYPE));
+///
+///     Ok(())
+/// }
+/// ```
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone)]
+pub struct Json<T>(pub T);
+
+#[cfg(feature = "serde")]
+#[async_trait]
+impl<T: serde::de::DeserializeOwned + Send + 'static> FromContext for Json<T> {
+    #[inline]
+    async fn from_context(ctx: &mut Context) -> FibraResult<Self> {
+        ctx.json().await.map(Self)
+    }
+}
+
+/// Url-encoded form body, the content type must be form, see Context::form_as
+///
+/// # Examples
+///
+/// ```
+/// use fibra::*;
+///
+/// #[derive(serde::Deserialize)]
+/// struct Login {
+///     user: String,
+///     remember: bool,
+/// }
+///
+/// #[tokio::main]
+/// async fn main() -> FibraResult<()> {
+///     let req = Request::new().header(header::CONTENT_TYPE, "application/x-www-form-urlencoded").body("user=fibra&remember=true");
+///     let Form(login) = Form::<Login>::from_context(&mut Context::from(req)).await?;
+///
+///     assert_eq!((login.user.as_str(), login.remember), ("fibra", true));
+///
+///     Ok(())
+/// }
+/// ```
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone)]
+pub struct Form<T>(pub T);
+
+#[cfg(feature = "serde")]
+#[async_trait]
+impl<T: serde::de::DeserializeOwned + Send + 'static> FromContext for Form<T> {
+    #[inline]
+    async fn from_context(ctx: &mut Context) -> FibraResult<Self> {
+        ctx.form_as().await.map(Self)
+    }
+}
+
+/// Typed header which can be decoded from a header value
+pub trait TypedHeader: Sized + Send + 'static {
+    /// The header name
+    fn name() -> HeaderName;
+
+    /// Decode from the header value
+    fn decode(val: &HeaderValue) -> Option<Self>;
+}
+
+/// The User-Agent header
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct UserAgent(pub String);
+
+impl TypedHeader for UserAgent {
+    #[inline]
+    fn name() -> HeaderName {
+        header::USER_AGENT
+    }
+
+    #[inline]
+    fn decode(val: &HeaderValue) -> Option<Self> {
+        val.to_str().ok().map(|v| Self(v.to_string()))
+    }
+}
+
+/// The Authorization header
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Authorization(pub String);
+
+impl TypedHeader for Authorization {
+    #[inline]
+    fn name() -> HeaderName {
+        header::AUTHORIZATION
+    }
+
+    #[inline]
+    fn decode(val: &HeaderValue) -> Option<Self> {
+        val.to_str().ok().map(|v| Self(v.to_string()))
+    }
+}
+
+/// The Content-Type header
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ContentType(pub Mime);
+
+impl TypedHeader for ContentType {
+    #[inline]
+    fn name() -> HeaderName {
+        header::CONTENT_TYPE
+    }
+
+    #[inline]
+    fn decode(val: &HeaderValue) -> Option<Self> {
+        val.to_str().ok()?.parse().ok().map(Self)
+    }
+}
+
+/// Typed header, missing or invalid headers are rejected
+///
+/// # Examples
+///
+/// ```
+/// use fibra::*;
+///
+/// #[tokio::main]
+/// async fn main() -> FibraResult<()> {
+///     let mut ctx = Context::from(Request::new().header(header::USER_AGENT, "curl/8.0").header(header::CONTENT_TYPE, "application/json"));
+///
+///     assert_eq!(Header::<UserAgent>::from_context(&mut ctx).await?.0, UserAgent("curl/8.0".to_string()));
+///     assert_eq!(Header::<ContentType>::from_context(&mut ctx).await?.0, ContentType(mime::APPLICATION_JSON));
+///     assert_eq!(Header::<Authorization>::from_context(&mut ctx).await.map_err(|err| err.status()).err(), Some(Status::BAD_REQUEST));
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Header<T>(pub T);
+
+#[async_trait]
+impl<T: TypedHeader> FromContext for Header<T> {
+    #[inline]
+    async fn from_context(ctx: &mut Context) -> FibraResult<Self> {
+        let name = T::name();
+
+        match ctx.header(&name) {
+            Some(val) => Ok(Self(T::decode(val).ok_or(Rejection::InvalidHeader(name))?)),
+            None => Err(Rejection::MissingHeader(name).into()),
+        }
+    }
+}
+
+/// All request headers
+#[async_trait]
+impl FromContext for HeaderMap {
+    #[inline]
+    async fn from_context(ctx: &mut Context) -> FibraResult<Self> {
+        Ok(ctx.headers().clone())
+    }
+}
+
+/// Shared state registered by Fibra::state, states are cloned so wrap large data in an Arc
+///
+/// # Examples
+///
+/// ```
+/// use fibra::*;
+///
+/// #[tokio::main]
+/// async fn main() -> FibraResult<()> {
+///     let mut app = Fibra::new();
+///     app.state(String::from("db pool"));
+///
+///     let mut ctx = Context::from(app);
+///
+///     assert_eq!(State::<String>::from_context(&mut ctx).await?.0, "db pool");
+///     assert_eq!(State::<u32>::from_context(&mut ctx).await.map_err(|err| err.status()).err(), Some(Status::INTERNAL_SERVER_ERROR));
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct State<T>(pub T);
+
+#[async_trait]
+impl<T: Clone + Send + Sync + 'static> FromContext for State<T> {
+    #[inline]
+    async fn from_context(ctx: &mut Context) -> FibraResult<Self> {
+        match ctx.state::<T>() {
+            Some(state) => Ok(Self(state.clone())),
+            None => Err(Rejection::MissingState(std::any::type_name::<T>()).into()),
+        }
+    }
+}
+
+/// The remote address that the connection comes from
+///
+/// # Examples
+///
+/// ```
+/// use fibra::*;
+/// use std::net::SocketAddr;
+///
+/// #[tokio::main]
+/// async fn main() -> FibraResult<()> {
+///     let mut ctx = Context::from(Connection::new().peeraddr(SocketAddr::from(([8, 8, 8, 8], 80))));
+///
+///     assert_eq!(RemoteAddr::from_context(&mut ctx).await?.0.to_string(), "8.8.8.8:80");
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct RemoteAddr(pub SocketAddr);
+
+#[async_trait]
+impl FromContext for RemoteAddr {
+    #[inline]
+    async fn from_context(ctx: &mut Context) -> FibraResult<Self> {
+        Ok(Self(*ctx.remote()))
+    }
+}
+
+/// The request body, it's taken from the context
+///
+/// # Examples
+///
+/// ```
+/// use fibra::*;
+///
+/// #[tokio::main]
+/// async fn main() -> FibraResult<()> {
+///     let mut ctx = Context::from(Request::new().body("Hello World!"));
+///
+///     assert_eq!(Body::from_context(&mut ctx).await?.read_all().await.unwrap_or_default(), "Hello World!");
+///     assert_eq!(ctx.read_all().await, None);
+///
+///     Ok(())
+/// }
+/// ```
+#[async_trait]
+impl FromContext for Body {
+    #[inline]
+    async fn from_context(ctx: &mut Context) -> FibraResult<Self> {
+        Ok(std::mem::take(ctx.req_mut().body_mut()))
+    }
+}
+
+/// A handler wrapper for functions taking extractors as arguments
+pub struct Extract<F, A> {
+    func: F,
+    args: std::marker::PhantomData<fn() -> A>,
+}
+
+/// Wrap a function taking extractors as arguments into a handler, arguments are extracted in
+/// order, any failure will stop the extraction and the error goes to the Catcher, such functions
+/// can also be registered directly without wrapping
+///
+/// # Examples
+///
+/// ```
+/// use fibra::*;
+///
+/// #[derive(Clone)]
+/// struct Db(&'static str);
+///
+/// async fn user(Param(id): Param<u32>, State(db): State<Db>, Header(agent): Header<UserAgent>) -> FibraResult<Response> {
+///     Ok(format!("{} {} {}", id, db.0, agent.0).into())
+/// }
+///
+/// #[tokio::main]
+/// async fn main() -> FibraResult<()> {
+///     let mut app = Fibra::new();
+///     app.state(Db("mysql"));
+///     app.get("/user/:id", user)?;
+///     app.get("/agent", extract(|Header(agent): Header<UserAgent>| async move { agent.0 }))?;
+///     app.catch(|res, err| res.body(err.map(|e| e.to_string()).unwrap_or_default()));
+///
+///     let app = std::sync::Arc::new(app);
+///     let run = |uri: &'static str| Context::new(app.clone(), Default::default(), Request::new().uri(uri).header(header::USER_AGENT, "curl")).next();
+///
+///     assert_eq!(run("http://localip.cc/user/123").await?.body_all().await.unwrap_or_default(), "123 mysql curl");
+///     assert_eq!(run("http://localip.cc/agent").await?.body_all().await.unwrap_or_default(), "curl");
+///
+///     let mut res = run("http://localip.cc/user/abc").await?;
+///
+///     assert_eq!(res.status_ref(), &Status::BAD_REQUEST);
+///     assert_eq!(res.body_all().await.unwrap_or_default(), "invalid param: invalid u32 value: \"abc\"");
+///
+///     Ok(())
+/// }
+/// ```
+#[inline]
+pub fn extract<F, A>(func: F) -> Extract<F, A> where Extract<F, A>: Handler {
+    Extract { func, args: std::marker::PhantomData }
+}
+
+/// The marker of functions taking extractors as arguments
+pub struct Extractors<A>(std::marker::PhantomData<fn() -> A>);
+
+macro_rules! impl_extract {
+    ($($arg:ident),*) => {
+        impl<F, R, $($arg,)*> IntoHandler<Extractors<($($arg,)*)>> for F
+        where
+            F: Fn($($arg),*) -> R + Send + Sync + 'static,
+            R: Future + Send + 'static,
+            R::Output: IntoResponse,
+            $($arg: FromContext,)*
+        {
+            type Handler = Extract<F, ($($arg,)*)>;
+
+            #[inline]
+            fn into_handler(self) -> Self::Handler {
+                extract(self)
+            }
+        }
+
+        #[async_trait]
+        impl<F, R, $($arg,)*> Handler for Extract<F, ($($arg,)*)>
+        where
+            F: Fn($($arg),*) -> R + Send + Sync + 'static,
+            R: Future + Send + 'static,
+            R::Output: IntoResponse,
+            $($arg: FromContext,)*
+        {
+            #[allow(non_snake_case, unused_mut, unused_variables)]
+            async fn handle(&self, mut ctx: Context) -> FibraResult<Response> {
+                $(let $arg = $arg::from_context(&mut ctx).await?;)*
+                (self.func)($($arg),*).await.into_response()
+            }
+        }
+    };
+}
+
+impl_extract!();
+impl_extract!(A1);
+impl_extract!(A1, A2);
+impl_extract!(A1, A2, A3);
+impl_extract!(A1, A2, A3, A4);
+impl_extract!(A1, A2, A3, A4, A5);
+impl_extract!(A1, A2, A3, A4, A5, A6);
+impl_extract!(A1, A2, A3, A4, A5, A6, A7);
+impl_extract!(A1, A2, A3, A4, A5, A6, A7, A8);
\ No newline at end of file
@@ -99,6 +99,25 @@ impl AsHandler for BoxHandler {
     }
 }
 
+/// Types that can be converted into a handler, the marker distinguishes handlers from functions
+/// taking extractors as arguments, so both can be registered directly
+pub trait IntoHandler<M>: Send + Sync + 'static {
+    /// The converted handler
+    type Handler: Handler;
+
+    /// Self -> Handler
+    fn into_handler(self) -> Self::Handler;
+}
+
+impl<T: Handler> IntoHandler<()> for T {
+    type Handler = T;
+
+    #[inline]
+    fn into_handler(self) -> Self::Handler {
+        self
+    }
+}
+
 /// Impl Handler for vector
 #[async_trait]
 impl<T: Handler> Handler for Vec<T> {
@@ -114,7 +133,9 @@ impl<T: Handler> Handler for Vec<T> {
     }
 }
 
-/// Impl Handler for async function and closure
+/// Impl Handler for async function and closure, any output that implements IntoResponse is allowed
+/// so `Ok(val.into())` can't be inferred, return the value directly or write
+/// `Ok::<_, FibraError>(val.into())` instead
 ///
 /// # Examples
 ///
@@ -131,13 +152,15 @@ impl<T: Handler> Handler for Vec<T> {
 ///     let ctx_closure = Context::default();
 ///
 ///     let fun_function = free_function;
-///     let fun_closure = |_ctx: Context| async { Ok("It Works!".into()) };
+///     let fun_closure = |_ctx: Context| async { "It Works!" };
+///     let fun_option = |ctx: Context| async move { ctx.header(header::USER_AGENT).map(|_| Status::OK) };
 ///
 ///     let mut res_function = fun_function.handle(ctx_function).await?;
 ///     let mut res_closure = fun_closure.handle(ctx_closure).await?;
 ///
 ///     assert_eq!(res_function.body_all().await.unwrap_or_default(), "Hello World!");
 ///     assert_eq!(res_closure.body_all().await.unwrap_or_default(), "It Works!");
+///     assert_eq!(fun_option.handle(Context::default()).await?.status_ref(), &Status::NOT_FOUND);
 ///
 ///     Ok(())
 /// }
@@ -146,11 +169,12 @@ impl<T: Handler> Handler for Vec<T> {
 impl<F, R> Handler for F
     where
         F: Fn(Context) -> R + Send + Sync + 'static,
-        R: Future<Output = FibraResult<Response>> + Send + 'static
+        R: Future + Send + 'static,
+        R::Output: IntoResponse
 {
     #[inline]
     async fn handle(&self, ctx: Context) -> FibraResult<Response> {
-        self(ctx).await
+        self(ctx).await.into_response()
     }
 }
 
@@ -2,22 +2,813 @@
 use crate::route::*;
 use crate::types::*;
 
-/// Limiter is a struct used for filtering HTTP requests
-#[derive(Default)]
+/// A composable predicate used to filter HTTP requests
+///
+/// # Examples
+///
+/// ```
+/// use fibra::*;
+///
+/// // POST and (header X or query Y) and not internal
+/// let limit = Limit::all(vec![
+///     Limit::method(Method::POST),
+///     Limit::any(vec![Limit::header("x-token", "abc"), Limit::query("token", "abc")]),
+///     Limit::not(Limit::named("internal", |ctx| ctx.subdomain() == "internal")),
+/// ]);
+///
+/// assert_eq!(limit.test(&Context::from(Request::new().method(Method::POST).uri("http://api.localip.cc/?token=abc"))), true);
+/// assert_eq!(limit.test(&Context::from(Request::new().method(Method::POST).uri("http://api.localip.cc").header("x-token", "abc"))), true);
+/// assert_eq!(limit.test(&Context::from(Request::new().method(Method::POST).uri("http://api.localip.cc"))), false);
+/// assert_eq!(limit.test(&Context::from(Request::new().method(Method::POST).uri("http://internal.localip.cc/?token=abc"))), false);
+/// assert_eq!(limit.test(&Context::from(Request::new().method(Method::GET).uri("http://api.localip.cc/?token=abc"))), false);
+///
+/// assert_eq!(limit.to_string(), r#"all(method(POST), any(header(x-token: abc), query(token=abc)), not(internal))"#);
+/// ```
+#[derive(Clone)]
+pub enum Limit {
+    /// A named predicate
+    Func(Cow<'static, str>, Arc<dyn Fn(&Context) -> bool + Send + Sync + 'static>),
+
+    /// Pass only if all predicates pass
+    All(Vec<Limit>),
+
+    /// Pass if any predicate passes
+    Any(Vec<Limit>),
+
+    /// Invert the predicate
+    Not(Box<Limit>),
+
+    /// Pass if the client accepts the mime type, routines are ranked by its quality
+    Accepts(Mime),
+
+    /// Reply with the status if the predicate fails and no other handler accepts the request
+    Reject(Status, Box<Limit>),
+}
+
+impl Limit {
+    /// Create an unnamed predicate
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let limit = Limit::func(|ctx| ctx.is_get());
+    ///
+    /// assert_eq!(limit.test(&Context::default()), true);
+    /// assert_eq!(limit.to_string(), "func");
+    /// ```
+    #[inline]
+    pub fn func<F>(f: F) -> Self where F: Fn(&Context) -> bool + Send + Sync + 'static {
+        Self::named("func", f)
+    }
+
+    /// Create a named predicate, the name is used when rendering
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let limit = Limit::named("is_get", |ctx| ctx.is_get());
+    ///
+    /// assert_eq!(limit.test(&Context::default()), true);
+    /// assert_eq!(limit.to_string(), "is_get");
+    /// ```
+    #[inline]
+    pub fn named<F>(name: impl Into<Cow<'static, str>>, f: F) -> Self where F: Fn(&Context) -> bool + Send + Sync + 'static {
+        Self::Func(name.into(), Arc::new(f))
+    }
+
+    /// Combine predicates, all must pass, an empty group always passes
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let limit = Limit::all(vec![Limit::method(Method::GET), Limit::path("/")]);
+    ///
+    /// assert_eq!(limit.test(&Context::default()), true);
+    /// assert_eq!(limit.test(&Context::from(Request::new().uri("/user"))), false);
+    /// assert_eq!(Limit::all(vec![]).test(&Context::default()), true);
+    /// ```
+    #[inline]
+    pub fn all(vec: Vec<Limit>) -> Self {
+        Self::All(vec)
+    }
+
+    /// Combine predicates, any pass will do, an empty group never passes
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let limit = Limit::any(vec![Limit::method(Method::PUT), Limit::path("/")]);
+    ///
+    /// assert_eq!(limit.test(&Context::default()), true);
+    /// assert_eq!(limit.test(&Context::from(Request::new().uri("/user"))), false);
+    /// assert_eq!(Limit::any(vec![]).test(&Context::default()), false);
+    /// ```
+    #[inline]
+    pub fn any(vec: Vec<Limit>) -> Self {
+        Self::Any(vec)
+    }
+
+    /// Invert a predicate
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let limit = Limit::not(Limit::method(Method::GET));
+    ///
+    /// assert_eq!(limit.test(&Context::default()), false);
+    /// assert_eq!(limit.test(&Context::from(Request::new().method(Method::POST))), true);
+    /// ```
+    #[allow(clippy::should_implement_trait)]
+    #[inline]
+    pub fn not(limit: Limit) -> Self {
+        Self::Not(Box::new(limit))
+    }
+
+    /// Bind a status to a predicate, if the predicate fails and no other handler accepts the
+    /// request, the router will reply with this status instead of NOT_FOUND
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> FibraResult<()> {
+    ///     let mut app = Fibra::new();
+    ///     app.get("/", "It Works!")?.limit().limit(Limit::reject(Status::FORBIDDEN, Limit::header("x-token", "abc")));
+    ///
+    ///     let ctx = Context::from((app, Request::new().uri("http://localip.cc/")));
+    ///
+    ///     assert_eq!(ctx.next().await?.status_ref(), &Status::FORBIDDEN);
+    ///     assert_eq!(Limit::reject(Status::FORBIDDEN, Limit::header("x-token", "abc")).to_string(), "reject(403, header(x-token: abc))");
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[inline]
+    pub fn reject(status: Status, limit: Limit) -> Self {
+        Self::Reject(status, Box::new(limit))
+    }
+
+    /// Check the predicate against the context
+    pub fn test(&self, ctx: &Context) -> bool {
+        match self {
+            Limit::Func(_, f) => f(ctx),
+            Limit::All(vec) => vec.iter().all(|limit| limit.test(ctx)),
+            Limit::Any(vec) => vec.iter().any(|limit| limit.test(ctx)),
+            Limit::Not(limit) => !limit.test(ctx),
+            Limit::Accepts(mime) => ctx.accepts(mime),
+            Limit::Reject(_, limit) => limit.test(ctx),
+        }
+    }
+
+    /// Check the predicate against the context, return the status bound to the failing part,
+    /// the outermost binding wins, an inverted predicate fails with its own binding
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let ctx = Context::from(Request::new().header(header::ACCEPT, "text/html"));
+    ///
+    /// assert_eq!(Limit::all(vec![Limit::path("/"), Limit::accepts(mime::APPLICATION_JSON)]).check(&ctx), Err(Some(Status::NOT_ACCEPTABLE)));
+    /// assert_eq!(Limit::any(vec![Limit::path("/user"), Limit::accepts(mime::APPLICATION_JSON)]).check(&ctx), Err(Some(Status::NOT_ACCEPTABLE)));
+    /// assert_eq!(Limit::not(Limit::reject(Status::FORBIDDEN, Limit::path("/"))).check(&ctx), Err(Some(Status::FORBIDDEN)));
+    /// assert_eq!(Limit::reject(Status::FORBIDDEN, Limit::accepts(mime::APPLICATION_JSON)).check(&ctx), Err(Some(Status::FORBIDDEN)));
+    /// assert_eq!(Limit::path("/user").check(&ctx), Err(None));
+    /// assert_eq!(Limit::accepts(mime::TEXT_HTML).check(&ctx), Ok(()));
+    /// ```
+    pub fn check(&self, ctx: &Context) -> Result<(), Option<Status>> {
+        match self {
+            Limit::All(vec) => vec.iter().try_for_each(|limit| limit.check(ctx)),
+            Limit::Any(vec) => {
+                let mut found = None;
+
+                for limit in vec {
+                    match limit.check(ctx) {
+                        Ok(()) => return Ok(()),
+                        Err(status) => found = found.or(status),
+                    }
+                }
+
+                Err(found)
+            }
+            Limit::Not(limit) => match limit.test(ctx) {
+                true => Err(limit.status()),
+                false => Ok(()),
+            },
+            Limit::Reject(status, limit) => limit.check(ctx).map_err(|_| Some(*status)),
+            _ => match self.test(ctx) {
+                true => Ok(()),
+                false => Err(None),
+            },
+        }
+    }
+
+    /// The quality of the best mime type the predicate accepts, None if it doesn't negotiate, a
+    /// conjunction takes the lowest quality of its parts and a disjunction takes the highest
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let ctx = Context::from(Request::new().header(header::ACCEPT, "text/html, application/json;q=0.5"));
+    /// let both = || vec![Limit::accepts(mime::TEXT_HTML), Limit::accepts(mime::APPLICATION_JSON)];
+    ///
+    /// assert_eq!(Limit::all(both()).quality(&ctx), Some(0.5));
+    /// assert_eq!(Limit::any(both()).quality(&ctx), Some(1.0));
+    /// assert_eq!(Limit::path("/").quality(&ctx), None);
+    /// ```
+    pub fn quality(&self, ctx: &Context) -> Option<f32> {
+        match self {
+            Limit::Func(..) | Limit::Not(_) => None,
+            Limit::All(vec) => vec.iter().filter_map(|limit| limit.quality(ctx)).reduce(f32::min),
+            Limit::Any(vec) => vec.iter().filter_map(|limit| limit.quality(ctx)).reduce(f32::max),
+            Limit::Accepts(mime) => Some(ctx.req().accept_quality(mime)),
+            Limit::Reject(_, limit) => limit.quality(ctx),
+        }
+    }
+
+    /// The status bound to the predicate itself
+    fn status(&self) -> Option<Status> {
+        match self {
+            Limit::Reject(status, _) => Some(*status),
+            _ => None,
+        }
+    }
+}
+
+impl Limit {
+    /// Limit method
+    #[inline]
+    pub fn method(val: impl Into<Method>) -> Self {
+        let val = val.into();
+        Self::named(format!("method({})", val), move |ctx| ctx.method() == val)
+    }
+
+    /// Limit domain name
+    #[inline]
+    pub fn domain(val: impl Into<Bytes>) -> Self {
+        let val = val.into();
+        Self::named(format!("domain({})", String::from_utf8_lossy(&val)), move |ctx| ctx.domain() == val)
+    }
+
+    /// Limit subdomain
+    #[inline]
+    pub fn subdomain(val: impl Into<Bytes>) -> Self {
+        let val = val.into();
+        Self::named(format!("subdomain({})", String::from_utf8_lossy(&val)), move |ctx| ctx.subdomain() == val)
+    }
+
+    /// Limit host
+    #[inline]
+    pub fn host(val: impl Into<Bytes>) -> Self {
+        let val = val.into();
+        Self::named(format!("host({})", String::from_utf8_lossy(&val)), move |ctx| ctx.host() == val)
+    }
+
+    /// Limit path
+    #[inline]
+    pub fn path(val: impl Into<Bytes>) -> Self {
+        let val = val.into();
+        Self::named(format!("path({})", String::from_utf8_lossy(&val)), move |ctx| ctx.path() == val)
+    }
+
+    /// Limit query
+    #[inline]
+    pub fn query(key: impl Into<String>, val: impl Into<Bytes>) -> Self {
+        let key = key.into();
+        let val = val.into();
+        Self::named(format!("query({}={})", key, String::from_utf8_lossy(&val)), move |ctx| ctx.query(key.as_str()) == val)
+    }
+
+    /// Limit header
+    #[inline]
+    pub fn header(key: impl IntoHeaderName, val: impl IntoHeaderValue) -> Self {
+        let key = key.into_header_name();
+        let val = val.into_header_value();
+        Self::named(format!("header({}: {})", key, String::from_utf8_lossy(val.as_bytes())), move |ctx| ctx.header(&key) == Some(&val))
+    }
+}
+
+impl Limit {
+    /// Limit the client's ip, ipv4-mapped ipv6 addresses are treated as ipv4
+    #[inline]
+    pub fn remote(val: impl Into<IpNet>) -> Self {
+        let val = val.into();
+        Self::named(format!("remote({})", val), move |ctx| val.contains(&ctx.remote().ip().to_canonical()))
+    }
+
+    /// Limit the local ip which accepted the connection
+    #[inline]
+    pub fn local(val: impl Into<IpNet>) -> Self {
+        let val = val.into();
+        Self::named(format!("local({})", val), move |ctx| val.contains(&ctx.local().ip().to_canonical()))
+    }
+
+    /// Limit the local port which accepted the connection
+    #[inline]
+    pub fn local_port(val: u16) -> Self {
+        Self::named(format!("local_port({})", val), move |ctx| ctx.local().port() == val)
+    }
+
+    /// Limit scheme
+    #[inline]
+    pub fn scheme(val: Scheme) -> Self {
+        Self::named(format!("scheme({})", val), move |ctx| ctx.scheme() == &val)
+    }
+
+    /// Limit http version
+    #[inline]
+    pub fn version(val: Version) -> Self {
+        Self::named(format!("version({:?})", val), move |ctx| ctx.version() == &val)
+    }
+
+    /// Limit the presence of tls
+    #[inline]
+    pub fn secure(val: bool) -> Self {
+        Self::named(format!("secure({})", val), move |ctx| ctx.is_secure() == val)
+    }
+
+    /// Limit the mime type which the client accepts, reply NOT_ACCEPTABLE if nothing fits,
+    /// among the routines of one path the one with the highest quality wins
+    #[inline]
+    pub fn accepts(val: Mime) -> Self {
+        Self::reject(Status::NOT_ACCEPTABLE, Self::Accepts(val))
+    }
+
+    /// Limit the content type of the request body, reply UNSUPPORTED_MEDIA_TYPE if nothing fits
+    #[inline]
+    pub fn content_type(val: Mime) -> Self {
+        Self::reject(Status::UNSUPPORTED_MEDIA_TYPE, Self::named(format!("content_type({})", val), move |ctx| {
+            ctx.content_type().map(|mime| content_match(&val, &mime)).unwrap_or(false)
+        }))
+    }
+}
+
+/// Glob options, * and ? never match a slash, ** crosses path segments
+const GLOB_OPTIONS: glob::MatchOptions = glob::MatchOptions { case_sensitive: true, require_literal_separator: true, require_literal_leading_dot: false };
+
+impl Limit {
+    /// Limit path with a glob pattern, * stays within one segment, ** crosses segments
+    #[inline]
+    pub fn path_glob(pattern: &str) -> FibraResult<Self> {
+        let glob = glob::Pattern::new(pattern)?;
+        Ok(Self::named(format!("path_glob({})", pattern), move |ctx| glob.matches_with(ctx.path(), GLOB_OPTIONS)))
+    }
+
+    /// Limit path with a regex pattern
+    #[inline]
+    pub fn path_regex(pattern: &str) -> FibraResult<Self> {
+        let regex = regex::Regex::new(pattern)?;
+        Ok(Self::named(format!("path_regex({})", pattern), move |ctx| regex.is_match(ctx.path())))
+    }
+
+    /// Limit host with a glob pattern
+    #[inline]
+    pub fn host_glob(pattern: &str) -> FibraResult<Self> {
+        let glob = glob::Pattern::new(pattern)?;
+        Ok(Self::named(format!("host_glob({})", pattern), move |ctx| glob.matches_with(ctx.host(), GLOB_OPTIONS)))
+    }
+
+    /// Limit host with a regex pattern
+    #[inline]
+    pub fn host_regex(pattern: &str) -> FibraResult<Self> {
+        let regex = regex::Regex::new(pattern)?;
+        Ok(Self::named(format!("host_regex({})", pattern), move |ctx| regex.is_match(ctx.host())))
+    }
+
+    /// Limit the presence of a query
+    #[inline]
+    pub fn query_present(key: impl Into<String>) -> Self {
+        let key = key.into();
+        Self::named(format!("query_present({})", key), move |ctx| ctx.queries().contains_key(key.as_str()))
+    }
+
+    /// Limit query with a regex pattern
+    #[inline]
+    pub fn query_regex(key: impl Into<String>, pattern: &str) -> FibraResult<Self> {
+        let key = key.into();
+        let regex = regex::Regex::new(pattern)?;
+        Ok(Self::named(format!("query_regex({}~{})", key, pattern), move |ctx| {
+            ctx.queries().get(key.as_str()).map(|val| regex.is_match(val)).unwrap_or(false)
+        }))
+    }
+
+    /// Limit the presence of a header
+    #[inline]
+    pub fn header_present(key: impl IntoHeaderName) -> Self {
+        let key = key.into_header_name();
+        Self::named(format!("header_present({})", key), move |ctx| ctx.header(&key).is_some())
+    }
+
+    /// Limit header with a prefix
+    #[inline]
+    pub fn header_prefix(key: impl IntoHeaderName, prefix: impl Into<Bytes>) -> Self {
+        let key = key.into_header_name();
+        let val = prefix.into();
+        Self::named(format!("header_prefix({}: {})", key, String::from_utf8_lossy(&val)), move |ctx| {
+            ctx.header(&key).map(|v| v.as_bytes().starts_with(&val)).unwrap_or(false)
+        })
+    }
+
+    /// Limit header with a regex pattern
+    #[inline]
+    pub fn header_regex(key: impl IntoHeaderName, pattern: &str) -> FibraResult<Self> {
+        let key = key.into_header_name();
+        let regex = regex::bytes::Regex::new(pattern)?;
+        Ok(Self::named(format!("header_regex({}~{})", key, pattern), move |ctx| {
+            ctx.header(&key).map(|v| regex.is_match(v.as_bytes())).unwrap_or(false)
+        }))
+    }
+}
+
+/// Check if the content type matches the rule, wildcards and params in the rule are respected
+fn content_match(rule: &Mime, mime: &Mime) -> bool {
+    let kind = rule.type_() == mime::STAR || rule.type_() == mime.type_();
+    let subtype = rule.subtype() == mime::STAR || (rule.subtype() == mime.subtype() && rule.suffix() == mime.suffix());
+
+    kind && subtype && rule.params().all(|(key, val)| mime.get_param(key).map(|v| v.as_str().eq_ignore_ascii_case(val.as_str())).unwrap_or(false))
+}
+
+impl std::ops::Not for Limit {
+    type Output = Limit;
+
+    #[inline]
+    fn not(self) -> Self::Output {
+        Limit::not(self)
+    }
+}
+
+impl Display for Limit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let group = |f: &mut Formatter<'_>, name: &str, vec: &Vec<Limit>| {
+            write!(f, "{}(", name)?;
+
+            for (idx, limit) in vec.iter().enumerate() {
+                if idx > 0 {
+                    write!(f, ", ")?;
+                }
+
+                write!(f, "{}", limit)?;
+            }
+
+            write!(f, ")")
+        };
+
+        match self {
+            Limit::Func(name, _) => write!(f, "{}", name),
+            Limit::All(vec) => group(f, "all", vec),
+            Limit::Any(vec) => group(f, "any", vec),
+            Limit::Not(limit) => write!(f, "not({})", limit),
+            Limit::Accepts(mime) => write!(f, "accepts({})", mime),
+            Limit::Reject(status, limit) => write!(f, "reject({}, {})", status.as_u16(), limit),
+        }
+    }
+}
+
+impl std::fmt::Debug for Limit {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Limit({})", self)
+    }
+}
+
+/// Limiter is a struct used for filtering HTTP requests, all limits must pass
+#[derive(Default, Clone)]
 pub struct Limiter {
     /// Store all filters
-    #[allow(clippy::type_complexity)]
-    limits: Vec<Box<dyn Fn(&Context) -> bool + Send + Sync + 'static>>
+    limits: Vec<Limit>
 }
 
 impl Limiter {
     /// Create a new object
     #[inline]
-    pub fn new() -> Self {
-        Self::default()
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert a filter
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let mut limiter = Limiter::new();
+    /// let context = Context::default();
+    ///
+    /// limiter.push(|ctx| ctx.is_get());
+    ///
+    /// assert_eq!(limiter.test(&context), true);
+    /// ```
+    #[inline]
+    pub fn push<F>(&mut self, f: F) -> &mut Self where F: Fn(&Context) -> bool + Send + Sync + 'static {
+        self.limit(Limit::func(f))
+    }
+
+    /// Insert a composable limit
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let mut limiter = Limiter::new();
+    /// limiter.method(Method::POST).limit(Limit::not(Limit::header("x-internal", "1")));
+    ///
+    /// assert_eq!(limiter.test(&Context::from(Request::new().method(Method::POST))), true);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().method(Method::POST).header("x-internal", "1"))), false);
+    /// assert_eq!(limiter.to_string(), "all(method(POST), not(header(x-internal: 1)))");
+    /// ```
+    #[inline]
+    pub fn limit(&mut self, limit: Limit) -> &mut Self {
+        self.limits.push(limit);
+        self
+    }
+
+    /// Get all limits
+    #[inline]
+    pub fn limits(&self) -> &Vec<Limit> {
+        &self.limits
+    }
+
+    /// Check filters, all of them must pass
+    #[inline]
+    pub fn test(&self, ctx: &Context) -> bool {
+        self.limits.iter().all(|limit| limit.test(ctx))
+    }
+
+    /// Check filters, return the bound status of the first failed one
+    #[inline]
+    pub fn check(&self, ctx: &Context) -> Result<(), Option<Status>> {
+        self.limits.iter().try_for_each(|limit| limit.check(ctx))
+    }
+
+    /// The lowest quality among the limits since all of them must pass, None if no limit
+    /// negotiates
+    #[inline]
+    pub fn quality(&self, ctx: &Context) -> Option<f32> {
+        self.limits.iter().filter_map(|limit| limit.quality(ctx)).reduce(f32::min)
+    }
+
+    /// Clear the limiter
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let mut limiter = Limiter::new();
+    /// let context = Context::default();
+    ///
+    /// limiter.push(|_| false);
+    ///
+    /// assert_eq!(limiter.test(&context), false);
+    ///
+    /// limiter.clear();
+    ///
+    /// assert_eq!(limiter.test(&context), true);
+    /// ```
+    #[inline]
+    pub fn clear(&mut self) -> &mut Self {
+        self.limits.clear();
+        self
+    }
+}
+
+impl Display for Limiter {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", Limit::All(self.limits.clone()))
+    }
+}
+
+impl std::fmt::Debug for Limiter {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Limiter({})", self)
+    }
+}
+
+impl Limiter {
+    /// Limit method
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let mut limiter = Limiter::new();
+    /// limiter.method(Method::PUT);
+    ///
+    /// assert_eq!(limiter.test(&Context::from(Request::new().method(Method::PUT))), true);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().method(Method::GET))), false);
+    /// ```
+    #[inline]
+    pub fn method(&mut self, val: impl Into<Method>) -> &mut Self {
+        self.limit(Limit::method(val))
+    }
+
+    /// Limit multiple methods, any pass will do
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let mut limiter = Limiter::new();
+    /// limiter.methods(vec![Method::PUT, Method::PATCH]);
+    ///
+    /// assert_eq!(limiter.test(&Context::from(Request::new().method(Method::PUT))), true);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().method(Method::PATCH))), true);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().method(Method::GET))), false);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().method(Method::POST))), false);
+    /// ```
+    #[inline]
+    pub fn methods(&mut self, vec: Vec<impl Into<Method>>) -> &mut Self {
+        self.limit(Limit::any(vec.into_iter().map(Limit::method).collect()))
+    }
+
+    /// Limit domain name
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let mut limiter = Limiter::new();
+    /// limiter.domain("localip.cc");
+    ///
+    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://www.localip.cc"))), true);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://www.example.net"))), false);
+    /// ```
+    #[inline]
+    pub fn domain(&mut self, val: impl Into<Bytes>) -> &mut Self {
+        self.limit(Limit::domain(val))
+    }
+
+    /// Limit domain names
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let mut limiter = Limiter::new();
+    /// limiter.domains(vec!["localip.cc", "example.net"]);
+    ///
+    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://www.localip.cc"))), true);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://www.example.net"))), true);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://www.example.org"))), false);
+    /// ```
+    #[inline]
+    pub fn domains(&mut self, vec: Vec<impl Into<Bytes>>) -> &mut Self {
+        self.limit(Limit::any(vec.into_iter().map(Limit::domain).collect()))
+    }
+
+    /// Limit subdomain
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let mut limiter = Limiter::new();
+    /// limiter.subdomain("api");
+    ///
+    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://api.localip.cc"))), true);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://www.localip.cc"))), false);
+    /// ```
+    #[inline]
+    pub fn subdomain(&mut self, val: impl Into<Bytes>) -> &mut Self {
+        self.limit(Limit::subdomain(val))
+    }
+
+    /// Limit subdomains
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let mut limiter = Limiter::new();
+    /// limiter.subdomains(vec!["api", "app"]);
+    ///
+    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://api.localip.cc"))), true);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://app.localip.cc"))), true);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://www.localip.cc"))), false);
+    /// ```
+    #[inline]
+    pub fn subdomains(&mut self, vec: Vec<impl Into<Bytes>>) -> &mut Self {
+        self.limit(Limit::any(vec.into_iter().map(Limit::subdomain).collect()))
+    }
+
+    /// Limit host
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let mut limiter = Limiter::new();
+    /// limiter.host("api.localip.cc");
+    ///
+    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://api.localip.cc"))), true);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://app.localip.cc"))), false);
+    /// ```
+    #[inline]
+    pub fn host(&mut self, val: impl Into<Bytes>) -> &mut Self {
+        self.limit(Limit::host(val))
+    }
+
+    /// Limit hosts
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let mut limiter = Limiter::new();
+    /// limiter.hosts(vec!["api.localip.cc", "app.localip.cc"]);
+    ///
+    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://api.localip.cc"))), true);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://app.localip.cc"))), true);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://www.localip.cc"))), false);
+    /// ```
+    #[inline]
+    pub fn hosts(&mut self, vec: Vec<impl Into<Bytes>>) -> &mut Self {
+        self.limit(Limit::any(vec.into_iter().map(Limit::host).collect()))
+    }
+
+    /// Limit path
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let mut limiter = Limiter::new();
+    /// limiter.path("/user/12345");
+    ///
+    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://api.localip.cc/user/12345"))), true);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://api.localip.cc/user/abcde"))), false);
+    /// ```
+    #[inline]
+    pub fn path(&mut self, val: impl Into<Bytes>) -> &mut Self {
+        self.limit(Limit::path(val))
+    }
+
+    /// Limit paths
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let mut limiter = Limiter::new();
+    /// limiter.paths(vec!["/user/12345", "/user/abcde"]);
+    ///
+    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://api.localip.cc/user/12345"))), true);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://api.localip.cc/user/abcde"))), true);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://api.localip.cc/user/qwert"))), false);
+    /// ```
+    #[inline]
+    pub fn paths(&mut self, vec: Vec<impl Into<Bytes>>) -> &mut Self {
+        self.limit(Limit::any(vec.into_iter().map(Limit::path).collect()))
+    }
+
+    /// Limit query
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let mut limiter = Limiter::new();
+    /// limiter.query("id", "12345");
+    ///
+    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://api.localip.cc/?id=12345"))), true);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://api.localip.cc/?id=abcde"))), false);
+    /// ```
+    #[inline]
+    pub fn query(&mut self, key: impl Into<String>, val: impl Into<Bytes>) -> &mut Self {
+        self.limit(Limit::query(key, val))
     }
 
-    /// Insert a filter
+    /// Limit queries
     ///
     /// # Examples
     ///
@@ -25,31 +816,36 @@ impl Limiter {
     /// use fibra::*;
     ///
     /// let mut limiter = Limiter::new();
-    /// let context = Context::default();
-    ///
-    /// limiter.push(|ctx| ctx.is_get());
+    /// limiter.queries(vec![("id", "12345"), ("id", "abcde")]);
     ///
-    /// assert_eq!(limiter.test(&context), true);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://api.localip.cc/?id=12345"))), true);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://api.localip.cc/?id=abcde"))), true);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://api.localip.cc/?id=qwert"))), false);
     /// ```
     #[inline]
-    pub fn push<F>(&mut self, f: F) -> &mut Self where F: Fn(&Context) -> bool + Send + Sync + 'static {
-        self.limits.push(Box::new(f));
-        self
+    pub fn queries(&mut self, vec: Vec<(impl Into<String>, impl Into<Bytes>)>) -> &mut Self {
+        self.limit(Limit::any(vec.into_iter().map(|(key, val)| Limit::query(key, val)).collect()))
     }
 
-    /// Check filters and return the first false result
+    /// Limit header
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let mut limiter = Limiter::new();
+    /// limiter.header("content-type", "text/plain");
+    ///
+    /// assert_eq!(limiter.test(&Context::from(Request::new().header("content-type", "text/plain"))), true);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().header("content-type", "application/json"))), false);
+    /// ```
     #[inline]
-    pub fn test(&self, ctx: &Context) -> bool {
-        self.limits.iter().find_map(|f| {
-            let pass = f(ctx);
-            match pass {
-                true => None,
-                false => Some(false),
-            }
-        }).unwrap_or(true)
+    pub fn header(&mut self, key: impl IntoHeaderName, val: impl IntoHeaderValue) -> &mut Self {
+        self.limit(Limit::header(key, val))
     }
 
-    /// Clear the limiter
+    /// Limit headers
     ///
     /// # Examples
     ///
@@ -57,108 +853,165 @@ impl Limiter {
     /// use fibra::*;
     ///
     /// let mut limiter = Limiter::new();
-    /// let context = Context::default();
+    /// limiter.headers(vec![("content-type", "text/plain"), ("content-type", "application/json")]);
     ///
-    /// limiter.push(|_| false);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().header("content-type", "text/plain"))), true);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().header("content-type", "application/json"))), true);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().header("content-type", "application/yaml"))), false);
+    /// ```
+    #[inline]
+    pub fn headers(&mut self, vec: Vec<(impl IntoHeaderName, impl IntoHeaderValue)>) -> &mut Self {
+        self.limit(Limit::any(vec.into_iter().map(|(key, val)| Limit::header(key, val)).collect()))
+    }
+
+    /// Limit client ip, ipv4 and ipv6 are both supported
     ///
-    /// assert_eq!(limiter.test(&context), false);
+    /// # Examples
     ///
-    /// limiter.clear();
+    /// ```
+    /// use fibra::*;
+    /// use std::net::SocketAddr;
     ///
-    /// assert_eq!(limiter.test(&context), true);
+    /// fn main() -> FibraResult<()> {
+    ///     let mut limiter = Limiter::new();
+    ///     limiter.remote("10.0.0.0/8".parse::<IpNet>()?);
+    ///
+    ///     let ctx = |ip: [u8; 4]| Context::from(Connection::new().peeraddr(SocketAddr::from((ip, 80))));
+    ///
+    ///     assert_eq!(limiter.test(&ctx([10, 0, 0, 1])), true);
+    ///     assert_eq!(limiter.test(&ctx([10, 255, 0, 1])), true);
+    ///     assert_eq!(limiter.test(&ctx([192, 168, 0, 1])), false);
+    ///
+    ///     Ok(())
+    /// }
     /// ```
     #[inline]
-    pub fn clear(&mut self) -> &mut Self {
-        self.limits.clear();
-        self
+    pub fn remote(&mut self, val: impl Into<IpNet>) -> &mut Self {
+        self.limit(Limit::remote(val))
     }
-}
 
-impl Limiter {
-    /// Limit method
+    /// Limit client ips, any pass will do
     ///
     /// # Examples
     ///
     /// ```
     /// use fibra::*;
+    /// use std::net::{IpAddr, SocketAddr};
     ///
-    /// let mut limiter = Limiter::new();
-    /// limiter.method(Method::PUT);
+    /// fn main() -> FibraResult<()> {
+    ///     let mut limiter = Limiter::new();
+    ///     limiter.remotes(vec!["10.0.0.0/8".parse::<IpNet>()?, "fd00::/8".parse::<IpNet>()?]);
     ///
-    /// assert_eq!(limiter.test(&Context::from(Request::new().method(Method::PUT))), true);
-    /// assert_eq!(limiter.test(&Context::from(Request::new().method(Method::GET))), false);
+    ///     let ctx = |ip: IpAddr| Context::from(Connection::new().peeraddr(SocketAddr::new(ip, 80)));
+    ///
+    ///     assert_eq!(limiter.test(&ctx("10.0.0.1".parse()?)), true);
+    ///     assert_eq!(limiter.test(&ctx("::ffff:10.0.0.1".parse()?)), true);
+    ///     assert_eq!(limiter.test(&ctx("fd12::1".parse()?)), true);
+    ///     assert_eq!(limiter.test(&ctx("8.8.8.8".parse()?)), false);
+    ///     assert_eq!(limiter.test(&ctx("2001:db8::1".parse()?)), false);
+    ///
+    ///     Ok(())
+    /// }
     /// ```
     #[inline]
-    pub fn method(&mut self, val: impl Into<Method>) -> &mut Self {
-        let val = val.into();
-
-        self.push(move |ctx| ctx.method() == val)
+    pub fn remotes(&mut self, vec: Vec<impl Into<IpNet>>) -> &mut Self {
+        self.limit(Limit::any(vec.into_iter().map(Limit::remote).collect()))
     }
 
-    /// Limit multiple methods, any pass will do
+    /// Block client ips, requests from any of them will fail
     ///
     /// # Examples
     ///
     /// ```
     /// use fibra::*;
+    /// use std::net::SocketAddr;
     ///
-    /// let mut limiter = Limiter::new();
-    /// limiter.methods(vec![Method::PUT, Method::PATCH]);
+    /// fn main() -> FibraResult<()> {
+    ///     let mut limiter = Limiter::new();
+    ///     limiter.deny_remotes(vec!["10.0.0.0/8".parse::<IpNet>()?]);
     ///
-    /// assert_eq!(limiter.test(&Context::from(Request::new().method(Method::PUT))), true);
-    /// assert_eq!(limiter.test(&Context::from(Request::new().method(Method::PATCH))), true);
-    /// assert_eq!(limiter.test(&Context::from(Request::new().method(Method::GET))), false);
-    /// assert_eq!(limiter.test(&Context::from(Request::new().method(Method::POST))), false);
+    ///     let ctx = |ip: [u8; 4]| Context::from(Connection::new().peeraddr(SocketAddr::from((ip, 80))));
+    ///
+    ///     assert_eq!(limiter.test(&ctx([10, 0, 0, 1])), false);
+    ///     assert_eq!(limiter.test(&ctx([8, 8, 8, 8])), true);
+    ///
+    ///     Ok(())
+    /// }
     /// ```
     #[inline]
-    pub fn methods(&mut self, vec: Vec<impl Into<Method>>) -> &mut Self {
-        let vec: Vec<Method> = vec.into_iter().map(Into::into).collect();
+    pub fn deny_remotes(&mut self, vec: Vec<impl Into<IpNet>>) -> &mut Self {
+        self.limit(Limit::not(Limit::any(vec.into_iter().map(Limit::remote).collect())))
+    }
 
-        self.push(move |ctx| vec.iter().any(|val| ctx.method() == val))
+    /// Limit the local ip which accepted the connection
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    /// use std::net::SocketAddr;
+    ///
+    /// fn main() -> FibraResult<()> {
+    ///     let mut limiter = Limiter::new();
+    ///     limiter.local("127.0.0.1/32".parse::<IpNet>()?);
+    ///
+    ///     let ctx = |ip: [u8; 4]| Context::from(Connection::new().sockaddr(SocketAddr::from((ip, 3000))));
+    ///
+    ///     assert_eq!(limiter.test(&ctx([127, 0, 0, 1])), true);
+    ///     assert_eq!(limiter.test(&ctx([192, 168, 1, 2])), false);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[inline]
+    pub fn local(&mut self, val: impl Into<IpNet>) -> &mut Self {
+        self.limit(Limit::local(val))
     }
 
-    /// Limit domain name
+    /// Limit the local port which accepted the connection
     ///
     /// # Examples
     ///
     /// ```
     /// use fibra::*;
+    /// use std::net::SocketAddr;
     ///
     /// let mut limiter = Limiter::new();
-    /// limiter.domain("localip.cc");
+    /// limiter.local_port(8080);
     ///
-    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://www.localip.cc"))), true);
-    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://www.example.net"))), false);
+    /// let ctx = |port: u16| Context::from(Connection::new().sockaddr(SocketAddr::from(([127, 0, 0, 1], port))));
+    ///
+    /// assert_eq!(limiter.test(&ctx(8080)), true);
+    /// assert_eq!(limiter.test(&ctx(3000)), false);
     /// ```
     #[inline]
-    pub fn domain(&mut self, val: impl Into<Bytes>) -> &mut Self {
-        let val = val.into();
-
-        self.push(move |ctx| ctx.domain() == val)
+    pub fn local_port(&mut self, val: u16) -> &mut Self {
+        self.limit(Limit::local_port(val))
     }
 
-    /// Limit domain names
+    /// Limit the local ports, any pass will do
     ///
     /// # Examples
     ///
     /// ```
     /// use fibra::*;
+    /// use std::net::SocketAddr;
     ///
     /// let mut limiter = Limiter::new();
-    /// limiter.domains(vec!["localip.cc", "example.net"]);
+    /// limiter.local_ports(vec![8080, 8443]);
     ///
-    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://www.localip.cc"))), true);
-    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://www.example.net"))), true);
-    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://www.example.org"))), false);
+    /// let ctx = |port: u16| Context::from(Connection::new().sockaddr(SocketAddr::from(([127, 0, 0, 1], port))));
+    ///
+    /// assert_eq!(limiter.test(&ctx(8080)), true);
+    /// assert_eq!(limiter.test(&ctx(8443)), true);
+    /// assert_eq!(limiter.test(&ctx(3000)), false);
     /// ```
     #[inline]
-    pub fn domains(&mut self, vec: Vec<impl Into<Bytes>>) -> &mut Self {
-        let vec: Vec<_> = vec.into_iter().map(Into::into).collect();
-
-        self.push(move |ctx| vec.iter().any(|val| ctx.domain() == val))
+    pub fn local_ports(&mut self, vec: Vec<u16>) -> &mut Self {
+        self.limit(Limit::any(vec.into_iter().map(Limit::local_port).collect()))
     }
 
-    /// Limit subdomain
+    /// Limit scheme
     ///
     /// # Examples
     ///
@@ -166,19 +1019,17 @@ impl Limiter {
     /// use fibra::*;
     ///
     /// let mut limiter = Limiter::new();
-    /// limiter.subdomain("api");
+    /// limiter.scheme(Scheme::HTTPS);
     ///
-    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://api.localip.cc"))), true);
-    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://www.localip.cc"))), false);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("https://localip.cc"))), true);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://localip.cc"))), false);
     /// ```
     #[inline]
-    pub fn subdomain(&mut self, val: impl Into<Bytes>) -> &mut Self {
-        let val = val.into();
-
-        self.push(move |ctx| ctx.subdomain() == val)
+    pub fn scheme(&mut self, val: Scheme) -> &mut Self {
+        self.limit(Limit::scheme(val))
     }
 
-    /// Limit subdomains
+    /// Limit http version
     ///
     /// # Examples
     ///
@@ -186,20 +1037,17 @@ impl Limiter {
     /// use fibra::*;
     ///
     /// let mut limiter = Limiter::new();
-    /// limiter.subdomains(vec!["api", "app"]);
+    /// limiter.version(Version::HTTP_2);
     ///
-    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://api.localip.cc"))), true);
-    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://app.localip.cc"))), true);
-    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://www.localip.cc"))), false);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().version(Version::HTTP_2))), true);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().version(Version::HTTP_11))), false);
     /// ```
     #[inline]
-    pub fn subdomains(&mut self, vec: Vec<impl Into<Bytes>>) -> &mut Self {
-        let vec: Vec<_> = vec.into_iter().map(Into::into).collect();
-
-        self.push(move |ctx| vec.iter().any(|val| ctx.subdomain() == val))
+    pub fn version(&mut self, val: Version) -> &mut Self {
+        self.limit(Limit::version(val))
     }
 
-    /// Limit host
+    /// Limit http versions, any pass will do
     ///
     /// # Examples
     ///
@@ -207,19 +1055,18 @@ impl Limiter {
     /// use fibra::*;
     ///
     /// let mut limiter = Limiter::new();
-    /// limiter.host("api.localip.cc");
+    /// limiter.versions(vec![Version::HTTP_10, Version::HTTP_11]);
     ///
-    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://api.localip.cc"))), true);
-    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://app.localip.cc"))), false);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().version(Version::HTTP_10))), true);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().version(Version::HTTP_11))), true);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().version(Version::HTTP_2))), false);
     /// ```
     #[inline]
-    pub fn host(&mut self, val: impl Into<Bytes>) -> &mut Self {
-        let val = val.into();
-
-        self.push(move |ctx| ctx.host() == val)
+    pub fn versions(&mut self, vec: Vec<Version>) -> &mut Self {
+        self.limit(Limit::any(vec.into_iter().map(Limit::version).collect()))
     }
 
-    /// Limit hosts
+    /// Limit the presence of tls
     ///
     /// # Examples
     ///
@@ -227,40 +1074,85 @@ impl Limiter {
     /// use fibra::*;
     ///
     /// let mut limiter = Limiter::new();
-    /// limiter.hosts(vec!["api.localip.cc", "app.localip.cc"]);
+    /// limiter.secure(true);
     ///
-    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://api.localip.cc"))), true);
-    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://app.localip.cc"))), true);
-    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://www.localip.cc"))), false);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("https://localip.cc"))), true);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://localip.cc"))), false);
     /// ```
     #[inline]
-    pub fn hosts(&mut self, vec: Vec<impl Into<Bytes>>) -> &mut Self {
-        let vec: Vec<_> = vec.into_iter().map(Into::into).collect();
-
-        self.push(move |ctx| vec.iter().any(|val| ctx.host() == val))
+    pub fn secure(&mut self, val: bool) -> &mut Self {
+        self.limit(Limit::secure(val))
     }
 
-    /// Limit path
+    /// Limit the mime type which the client accepts, q-values and wildcards are respected, the
+    /// routine with the highest quality among one path wins, the router replies NOT_ACCEPTABLE
+    /// if no other handler accepts the request
     ///
     /// # Examples
     ///
     /// ```
     /// use fibra::*;
     ///
-    /// let mut limiter = Limiter::new();
-    /// limiter.path("/user/12345");
+    /// #[tokio::main]
+    /// async fn main() -> FibraResult<()> {
+    ///     let mut app = Fibra::new();
+    ///     app.get("/user", "v1")?.limit().accepts("application/vnd.acme.v1+json".parse()?);
+    ///     app.get("/user", "v2")?.limit().accepts("application/vnd.acme.v2+json".parse()?);
     ///
-    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://api.localip.cc/user/12345"))), true);
-    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://api.localip.cc/user/abcde"))), false);
+    ///     let app = std::sync::Arc::new(app);
+    ///     let req = |accept: &'static str| Request::new().uri("http://localip.cc/user").header(header::ACCEPT, accept);
+    ///
+    ///     assert_eq!(Context::new(app.clone(), Default::default(), req("application/vnd.acme.v2+json")).next().await?.body_all().await.unwrap_or_default(), "v2");
+    ///     assert_eq!(Context::new(app.clone(), Default::default(), req("application/vnd.acme.v1+json, */*;q=0.1")).next().await?.body_all().await.unwrap_or_default(), "v1");
+    ///     assert_eq!(Context::new(app.clone(), Default::default(), req("application/vnd.acme.v1+json;q=0.1, application/vnd.acme.v2+json")).next().await?.body_all().await.unwrap_or_default(), "v2");
+    ///     assert_eq!(Context::new(app.clone(), Default::default(), req("text/html")).next().await?.status_ref(), &Status::NOT_ACCEPTABLE);
+    ///
+    ///     // the next best one runs if the winner passes
+    ///     let mut app = Fibra::new();
+    ///     app.get("/user", "v1")?.limit().accepts("application/vnd.acme.v1+json".parse()?);
+    ///     app.get("/user", |ctx: Context| async move { ctx.next().await })?.limit().accepts("application/vnd.acme.v2+json".parse()?);
+    ///
+    ///     let mut res = Context::from((app, req("application/vnd.acme.v1+json;q=0.1, application/vnd.acme.v2+json"))).next().await?;
+    ///     assert_eq!(res.body_all().await.unwrap_or_default(), "v1");
+    ///
+    ///     Ok(())
+    /// }
     /// ```
     #[inline]
-    pub fn path(&mut self, val: impl Into<Bytes>) -> &mut Self {
-        let val = val.into();
+    pub fn accepts(&mut self, val: Mime) -> &mut Self {
+        self.limit(Limit::accepts(val))
+    }
 
-        self.push(move |ctx| ctx.path() == val)
+    /// Limit the content type of the request body, the router replies UNSUPPORTED_MEDIA_TYPE
+    /// if no other handler accepts the request
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> FibraResult<()> {
+    ///     let mut app = Fibra::new();
+    ///     app.post("/user", "json")?.limit().content_type(mime::APPLICATION_JSON);
+    ///     app.post("/user", "form")?.limit().content_type(mime::APPLICATION_WWW_FORM_URLENCODED);
+    ///
+    ///     let app = std::sync::Arc::new(app);
+    ///     let req = |kind: &'static str| Request::new().method(Method::POST).uri("http://localip.cc/user").header(header::CONTENT_TYPE, kind);
+    ///
+    ///     assert_eq!(Context::new(app.clone(), Default::default(), req("application/json; charset=utf-8")).next().await?.body_all().await.unwrap_or_default(), "json");
+    ///     assert_eq!(Context::new(app.clone(), Default::default(), req("application/x-www-form-urlencoded")).next().await?.body_all().await.unwrap_or_default(), "form");
+    ///     assert_eq!(Context::new(app.clone(), Default::default(), req("text/plain")).next().await?.status_ref(), &Status::UNSUPPORTED_MEDIA_TYPE);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[inline]
+    pub fn content_type(&mut self, val: Mime) -> &mut Self {
+        self.limit(Limit::content_type(val))
     }
 
-    /// Limit paths
+    /// Limit the content types of the request body, any pass will do
     ///
     /// # Examples
     ///
@@ -268,41 +1160,122 @@ impl Limiter {
     /// use fibra::*;
     ///
     /// let mut limiter = Limiter::new();
-    /// limiter.paths(vec!["/user/12345", "/user/abcde"]);
+    /// limiter.content_types(vec![mime::APPLICATION_JSON, mime::TEXT_STAR]);
     ///
-    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://api.localip.cc/user/12345"))), true);
-    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://api.localip.cc/user/abcde"))), true);
-    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://api.localip.cc/user/qwert"))), false);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().header(header::CONTENT_TYPE, "application/json"))), true);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().header(header::CONTENT_TYPE, "text/csv"))), true);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().header(header::CONTENT_TYPE, "image/png"))), false);
+    /// assert_eq!(limiter.check(&Context::from(Request::new())), Err(Some(Status::UNSUPPORTED_MEDIA_TYPE)));
     /// ```
     #[inline]
-    pub fn paths(&mut self, vec: Vec<impl Into<Bytes>>) -> &mut Self {
-        let vec: Vec<_> = vec.into_iter().map(Into::into).collect();
+    pub fn content_types(&mut self, vec: Vec<Mime>) -> &mut Self {
+        self.limit(Limit::reject(Status::UNSUPPORTED_MEDIA_TYPE, Limit::any(vec.into_iter().map(Limit::content_type).collect())))
+    }
 
-        self.push(move |ctx| vec.iter().any(|val| ctx.path() == val))
+    /// Limit path with a glob pattern, the pattern is compiled only once, * and ? stay within one
+    /// segment, use ** to cross segments
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// fn main() -> FibraResult<()> {
+    ///     let mut limiter = Limiter::new();
+    ///     limiter.path_glob("/static/**/*.js")?;
+    ///
+    ///     assert_eq!(limiter.test(&Context::from(Request::new().uri("http://localip.cc/static/js/app.js"))), true);
+    ///     assert_eq!(limiter.test(&Context::from(Request::new().uri("http://localip.cc/static/app.css"))), false);
+    ///     assert_eq!(Limiter::new().path_glob("/[a").is_err(), true);
+    ///
+    ///     let mut limiter = Limiter::new();
+    ///     limiter.path_glob("/api/*")?;
+    ///
+    ///     assert_eq!(limiter.test(&Context::from(Request::new().uri("http://localip.cc/api/a"))), true);
+    ///     assert_eq!(limiter.test(&Context::from(Request::new().uri("http://localip.cc/api/a/b/c"))), false);
+    ///
+    ///     let mut limiter = Limiter::new();
+    ///     limiter.path_glob("/api/**")?;
+    ///
+    ///     assert_eq!(limiter.test(&Context::from(Request::new().uri("http://localip.cc/api/a/b/c"))), true);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[inline]
+    pub fn path_glob(&mut self, pattern: &str) -> FibraResult<&mut Self> {
+        Ok(self.limit(Limit::path_glob(pattern)?))
     }
 
-    /// Limit query
+    /// Limit path with a regex pattern, use ^ and $ to match the whole path
     ///
     /// # Examples
     ///
     /// ```
     /// use fibra::*;
     ///
-    /// let mut limiter = Limiter::new();
-    /// limiter.query("id", "12345");
+    /// fn main() -> FibraResult<()> {
+    ///     let mut limiter = Limiter::new();
+    ///     limiter.path_regex(r"^/user/\d+$")?;
     ///
-    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://api.localip.cc/?id=12345"))), true);
-    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://api.localip.cc/?id=abcde"))), false);
+    ///     assert_eq!(limiter.test(&Context::from(Request::new().uri("http://localip.cc/user/12345"))), true);
+    ///     assert_eq!(limiter.test(&Context::from(Request::new().uri("http://localip.cc/user/abcde"))), false);
+    ///
+    ///     Ok(())
+    /// }
     /// ```
     #[inline]
-    pub fn query(&mut self, key: impl Into<String>, val: impl Into<Bytes>) -> &mut Self {
-        let key = key.into();
-        let val = val.into();
+    pub fn path_regex(&mut self, pattern: &str) -> FibraResult<&mut Self> {
+        Ok(self.limit(Limit::path_regex(pattern)?))
+    }
 
-        self.push(move |ctx| ctx.query(key.as_str()) == val)
+    /// Limit host with a glob pattern
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// fn main() -> FibraResult<()> {
+    ///     let mut limiter = Limiter::new();
+    ///     limiter.host_glob("canary-*.localip.cc")?;
+    ///
+    ///     assert_eq!(limiter.test(&Context::from(Request::new().uri("http://canary-01.localip.cc"))), true);
+    ///     assert_eq!(limiter.test(&Context::from(Request::new().uri("http://www.localip.cc"))), false);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[inline]
+    pub fn host_glob(&mut self, pattern: &str) -> FibraResult<&mut Self> {
+        Ok(self.limit(Limit::host_glob(pattern)?))
     }
 
-    /// Limit queries
+    /// Limit host with a regex pattern, use ^ and $ to match the whole host
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// fn main() -> FibraResult<()> {
+    ///     let mut limiter = Limiter::new();
+    ///     limiter.host_regex(r"^(api|app)\.localip\.cc$")?;
+    ///
+    ///     assert_eq!(limiter.test(&Context::from(Request::new().uri("http://api.localip.cc"))), true);
+    ///     assert_eq!(limiter.test(&Context::from(Request::new().uri("http://app.localip.cc"))), true);
+    ///     assert_eq!(limiter.test(&Context::from(Request::new().uri("http://www.localip.cc"))), false);
+    ///     assert_eq!(Limiter::new().host_regex("(").is_err(), true);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[inline]
+    pub fn host_regex(&mut self, pattern: &str) -> FibraResult<&mut Self> {
+        Ok(self.limit(Limit::host_regex(pattern)?))
+    }
+
+    /// Limit the presence of a query, its value is ignored
     ///
     /// # Examples
     ///
@@ -310,20 +1283,41 @@ impl Limiter {
     /// use fibra::*;
     ///
     /// let mut limiter = Limiter::new();
-    /// limiter.queries(vec![("id", "12345"), ("id", "abcde")]);
+    /// limiter.query_present("canary");
     ///
-    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://api.localip.cc/?id=12345"))), true);
-    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://api.localip.cc/?id=abcde"))), true);
-    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://api.localip.cc/?id=qwert"))), false);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://localip.cc/?canary"))), true);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://localip.cc/?canary=1"))), true);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().uri("http://localip.cc/?stable=1"))), false);
     /// ```
     #[inline]
-    pub fn queries(&mut self, vec: Vec<(impl Into<String>, impl Into<Bytes>)>) -> &mut Self {
-        let vec: Vec<_> = vec.into_iter().map(|(key, val)| (key.into(), val.into())).collect();
+    pub fn query_present(&mut self, key: impl Into<String>) -> &mut Self {
+        self.limit(Limit::query_present(key))
+    }
 
-        self.push(move |ctx| vec.iter().any(|(key, val)| ctx.query(key.as_str()) == val))
+    /// Limit query with a regex pattern, requests without the query will fail
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// fn main() -> FibraResult<()> {
+    ///     let mut limiter = Limiter::new();
+    ///     limiter.query_regex("id", r"^\d+$")?;
+    ///
+    ///     assert_eq!(limiter.test(&Context::from(Request::new().uri("http://localip.cc/?id=12345"))), true);
+    ///     assert_eq!(limiter.test(&Context::from(Request::new().uri("http://localip.cc/?id=abcde"))), false);
+    ///     assert_eq!(limiter.test(&Context::from(Request::new().uri("http://localip.cc/"))), false);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[inline]
+    pub fn query_regex(&mut self, key: impl Into<String>, pattern: &str) -> FibraResult<&mut Self> {
+        Ok(self.limit(Limit::query_regex(key, pattern)?))
     }
 
-    /// Limit header
+    /// Limit the presence of a header, its value is ignored
     ///
     /// # Examples
     ///
@@ -331,19 +1325,17 @@ impl Limiter {
     /// use fibra::*;
     ///
     /// let mut limiter = Limiter::new();
-    /// limiter.header("content-type", "text/plain");
+    /// limiter.header_present(header::AUTHORIZATION);
     ///
-    /// assert_eq!(limiter.test(&Context::from(Request::new().header("content-type", "text/plain"))), true);
-    /// assert_eq!(limiter.test(&Context::from(Request::new().header("content-type", "application/json"))), false);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().header(header::AUTHORIZATION, "Bearer abc"))), true);
+    /// assert_eq!(limiter.test(&Context::from(Request::new())), false);
     /// ```
     #[inline]
-    pub fn header(&mut self, key: impl IntoHeaderName, val: impl IntoHeaderValue) -> &mut Self {
-        let key = key.into_header_name();
-        let val = val.into_header_value();
-        self.push(move |ctx| ctx.header(&key) == Some(&val))
+    pub fn header_present(&mut self, key: impl IntoHeaderName) -> &mut Self {
+        self.limit(Limit::header_present(key))
     }
 
-    /// Limit headers
+    /// Limit header with a prefix
     ///
     /// # Examples
     ///
@@ -351,16 +1343,37 @@ impl Limiter {
     /// use fibra::*;
     ///
     /// let mut limiter = Limiter::new();
-    /// limiter.headers(vec![("content-type", "text/plain"), ("content-type", "application/json")]);
+    /// limiter.header_prefix(header::AUTHORIZATION, "Bearer ");
     ///
-    /// assert_eq!(limiter.test(&Context::from(Request::new().header("content-type", "text/plain"))), true);
-    /// assert_eq!(limiter.test(&Context::from(Request::new().header("content-type", "application/json"))), true);
-    /// assert_eq!(limiter.test(&Context::from(Request::new().header("content-type", "application/yaml"))), false);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().header(header::AUTHORIZATION, "Bearer abc"))), true);
+    /// assert_eq!(limiter.test(&Context::from(Request::new().header(header::AUTHORIZATION, "Basic abc"))), false);
+    /// assert_eq!(limiter.test(&Context::from(Request::new())), false);
     /// ```
     #[inline]
-    pub fn headers(&mut self, vec: Vec<(impl IntoHeaderName, impl IntoHeaderValue)>) -> &mut Self {
-        let vec: Vec<_> = vec.into_iter().map(|(key, val)| (key.into_header_name(), val.into_header_value())).collect();
+    pub fn header_prefix(&mut self, key: impl IntoHeaderName, prefix: impl Into<Bytes>) -> &mut Self {
+        self.limit(Limit::header_prefix(key, prefix))
+    }
 
-        self.push(move |ctx| vec.iter().any(|(key, val)| ctx.header(key) == Some(val)))
+    /// Limit header with a regex pattern, requests without the header will fail
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// fn main() -> FibraResult<()> {
+    ///     let mut limiter = Limiter::new();
+    ///     limiter.header_regex(header::USER_AGENT, r"(?i)bot|crawler|spider")?;
+    ///
+    ///     assert_eq!(limiter.test(&Context::from(Request::new().header(header::USER_AGENT, "Googlebot/2.1"))), true);
+    ///     assert_eq!(limiter.test(&Context::from(Request::new().header(header::USER_AGENT, "Mozilla/5.0"))), false);
+    ///     assert_eq!(limiter.test(&Context::from(Request::new())), false);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[inline]
+    pub fn header_regex(&mut self, key: impl IntoHeaderName, pattern: &str) -> FibraResult<&mut Self> {
+        Ok(self.limit(Limit::header_regex(key, pattern)?))
     }
 }
\ No newline at end of file
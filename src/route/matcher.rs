@@ -29,6 +29,33 @@ impl Matcher {
 
         Ok(list.last_mut().unwrap_or_else(|| unreachable!()))
     }
+
+    /// Reorder the passing routines which negotiate by the quality of their accepted mime types,
+    /// the best one runs first and the others follow if it calls next, the rest keep their places
+    fn negotiate(routes: &Vec<Routine>, ctx: &mut Context) {
+        if routes.len() < 2 {
+            return;
+        }
+
+        let ranked: Vec<_> = routes.iter().enumerate()
+            .filter_map(|(idx, routine)| routine.quality(ctx).filter(|q| *q > 0.0 && routine.test(ctx)).map(|q| (idx, q)))
+            .collect();
+
+        let mut sorted = ranked.clone();
+        sorted.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        if sorted == ranked {
+            return;
+        }
+
+        let mut order: Vec<usize> = (0..routes.len()).collect();
+
+        for ((slot, _), (idx, _)) in ranked.iter().zip(sorted.iter()) {
+            order[*slot] = *idx;
+        }
+
+        ctx.rank(routes, order);
+    }
 }
 
 #[async_trait]
@@ -43,6 +70,8 @@ impl Handler for Matcher {
                 ctx.params_mut().extend(new);
             }
 
+            Self::negotiate(routes, &mut ctx);
+
             return routes.handle(ctx).await;
         }
 
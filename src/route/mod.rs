@@ -1,13 +1,17 @@
 //! Routing Rore
+mod blocking;
 mod catcher;
 mod context;
+mod extractor;
 mod handler;
 mod limiter;
 mod matcher;
 mod routine;
 
+pub use blocking::*;
 pub use catcher::*;
 pub use context::*;
+pub use extractor::*;
 pub use handler::*;
 pub use limiter::*;
 pub use matcher::*;
@@ -1,5 +1,5 @@
 //! Store HTTP route info
-use crate::{FibraResult, Response};
+use crate::types::*;
 use crate::route::*;
 
 /// A struct that stores HTTP route information
@@ -24,6 +24,18 @@ impl Routine {
         self.limiter.get_or_insert(Limiter::new())
     }
 
+    /// Check if the limits pass
+    #[inline]
+    pub(crate) fn test(&self, ctx: &Context) -> bool {
+        self.limiter.as_ref().map(|limiter| limiter.test(ctx)).unwrap_or(true)
+    }
+
+    /// The best quality of the accepted mime types, None if the routine doesn't negotiate
+    #[inline]
+    pub(crate) fn quality(&self, ctx: &Context) -> Option<f32> {
+        self.limiter.as_ref().and_then(|limiter| limiter.quality(ctx))
+    }
+
     /// Treat the handler as type T
     #[inline]
     pub fn treat<T: Handler>(&mut self) -> Option<&mut T> {
@@ -34,13 +46,27 @@ impl Routine {
 #[async_trait]
 impl Handler for Routine {
     #[inline]
-    async fn handle(&self, ctx: Context) -> FibraResult<Response> {
+    async fn handle(&self, mut ctx: Context) -> FibraResult<Response> {
         if let Some(limiter) = &self.limiter {
-            if !limiter.test(&ctx) {
+            if let Err(status) = limiter.check(&ctx) {
+                if let Some(status) = status {
+                    ctx.rejected(status);
+                }
+
                 return ctx.next().await;
             }
         }
 
+        // reject oversized bodies up front, clients sending Expect: 100-continue won't upload it,
+        // only routes do this since mounted handlers run before any route matches
+        if let Some(max) = ctx.req().body_ref().limit_ref() {
+            let length = ctx.header(header::CONTENT_LENGTH).and_then(|v| v.to_str().ok()).and_then(|v| v.parse::<u64>().ok());
+
+            if length.map(|len| len > max as u64).unwrap_or(false) {
+                return Err(Rejection::PayloadTooLarge(max).into());
+            }
+        }
+
         self.handler.handle(ctx).await
     }
 }
\ No newline at end of file
@@ -6,9 +6,29 @@ use http_body_util::BodyExt;
 /// Hyper Body
 pub type BoxBody = http_body_util::combinators::BoxBody<Bytes, FibraError>;
 
+/// The default size limit of bodies, used by the root router and by body decoders when the
+/// body has no limit
+pub(crate) const BODY_LIMIT: usize = 16 * 1024 * 1024;
+
+/// One frame of the body
+#[derive(Debug, Clone, PartialEq)]
+pub enum BodyFrame {
+    /// The data frame
+    Data(Bytes),
+
+    /// The trailers frame, it's the last frame if present
+    Trailers(HeaderMap),
+}
+
 /// HTTP Body
 #[derive(Default)]
-pub struct Body(BoxBody);
+pub struct Body {
+    inner: BoxBody,
+    limit: Option<usize>,
+    timeout: Option<std::time::Duration>,
+    length: usize,
+    trailers: Option<HeaderMap>,
+}
 
 impl Body {
     /// Create a new object
@@ -17,15 +37,76 @@ impl Body {
         Self::default()
     }
 
-    /// Read all bytes
+    /// Limit the size of the body, reading beyond max fails with PAYLOAD_TOO_LARGE, the limit can
+    /// only be lowered, the smaller one wins if it's called multiple times
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> FibraResult<()> {
+    ///     let mut body = Body::from("Hello World!").limit(4);
+    ///
+    ///     assert_eq!(body.try_read_all().await.map_err(|err| err.status()).err(), Some(Status::PAYLOAD_TOO_LARGE));
+    ///     assert_eq!(Body::from("Hello World!").limit(4).read_all().await, None);
+    ///     assert_eq!(Body::from("Hello World!").limit(12).read_all().await.unwrap_or_default(), "Hello World!");
+    ///     assert_eq!(Body::from("Hello World!").limit(4).limit(12).limit_ref(), Some(4));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[inline]
+    pub fn limit(mut self, max: usize) -> Self {
+        self.limit = Some(self.limit.map_or(max, |cur| cur.min(max)));
+        self
+    }
+
+    /// Get the size limit
+    #[inline]
+    pub fn limit_ref(&self) -> Option<usize> {
+        self.limit
+    }
+
+    /// Limit the time of waiting for each frame, a slow read fails with REQUEST_TIMEOUT, the
+    /// timeout can only be shortened, the smaller one wins if it's called multiple times
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    /// use std::time::Duration;
+    /// use http_body_util::BodyExt;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> FibraResult<()> {
+    ///     let slow = http_body_util::StreamBody::new(futures::stream::pending::<FibraResult<hyper::body::Frame<bytes::Bytes>>>());
+    ///     let mut body = Body::from(slow.boxed()).timeout(Duration::from_millis(10));
+    ///
+    ///     assert_eq!(body.try_read_all().await.map_err(|err| err.status()).err(), Some(Status::REQUEST_TIMEOUT));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[inline]
+    pub fn timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.timeout = Some(self.timeout.map_or(timeout, |cur| cur.min(timeout)));
+        self
+    }
+
+    /// Get the read timeout
+    #[inline]
+    pub fn timeout_ref(&self) -> Option<std::time::Duration> {
+        self.timeout
+    }
+
+    /// Read all bytes, None if the body is empty or fails to read
     #[inline]
     pub async fn read_all(&mut self) -> Option<Bytes> {
-        let first = match self.read_frame().await {
-            Some(obj) => obj,
-            None => return None,
-        };
+        let first = self.try_read_frame().await.ok()??;
 
-        let second = match self.read_frame().await {
+        let second = match self.try_read_frame().await.ok()? {
             Some(obj) => obj,
             None => return Some(first),
         };
@@ -35,72 +116,219 @@ impl Body {
         whole.extend(first);
         whole.extend(second);
 
-        while let Some(bytes) = self.read_frame().await {
+        while let Some(bytes) = self.try_read_frame().await.ok()? {
             whole.extend(bytes);
         }
 
         Some(whole.freeze())
     }
 
-    /// Read one frame
+    /// Read all bytes, bodies larger than max are rejected with PAYLOAD_TOO_LARGE
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> FibraResult<()> {
+    ///     assert_eq!(Body::from("Hello World!").read_limit(16).await?, "Hello World!");
+    ///     assert_eq!(Body::from("Hello World!").read_limit(4).await.map_err(|err| err.status()).err(), Some(Status::PAYLOAD_TOO_LARGE));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn read_limit(&mut self, max: usize) -> FibraResult<Bytes> {
+        let mut whole = BytesMut::new();
+
+        while let Some(bytes) = self.try_read_frame().await? {
+            if whole.len() + bytes.len() > max {
+                return Err(Rejection::PayloadTooLarge(max).into());
+            }
+
+            whole.extend(bytes);
+        }
+
+        Ok(whole.freeze())
+    }
+
+    /// Read one data frame, None if the body is finished or fails to read
     #[inline]
     pub async fn read_frame(&mut self) -> Option<Bytes> {
-        self.0.frame().await?.ok().and_then(|frame| frame.into_data().ok())
+        self.try_read_frame().await.ok().flatten()
+    }
+
+    /// Read all bytes, errors like disconnection are returned instead of a partial body
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    /// use http_body_util::BodyExt;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> FibraResult<()> {
+    ///     assert_eq!(Body::from("Hello World!").try_read_all().await?, "Hello World!");
+    ///     assert_eq!(Body::new().try_read_all().await?, "");
+    ///
+    ///     let broken = futures::stream::iter(vec![Ok(hyper::body::Frame::data(bytes::Bytes::from("Hello"))), Err(FibraError::PanicError("disconnected".into()))]);
+    ///     let mut body = Body::from(http_body_util::StreamBody::new(broken).boxed());
+    ///
+    ///     assert_eq!(body.try_read_all().await.map_err(|err| err.to_string()), Err("disconnected".to_string()));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[inline]
+    pub async fn try_read_all(&mut self) -> FibraResult<Bytes> {
+        self.read_limit(usize::MAX).await
+    }
+
+    /// Read one data frame, trailers are skipped and kept for later access
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> FibraResult<()> {
+    ///     let mut body = Body::from("Hello World!");
+    ///
+    ///     assert_eq!(body.try_read_frame().await?, Some(bytes::Bytes::from("Hello World!")));
+    ///     assert_eq!(body.try_read_frame().await?, None);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn try_read_frame(&mut self) -> FibraResult<Option<Bytes>> {
+        loop {
+            match self.try_read_raw().await? {
+                Some(BodyFrame::Data(data)) => return Ok(Some(data)),
+                Some(BodyFrame::Trailers(_)) => continue,
+                None => return Ok(None),
+            }
+        }
+    }
+
+    /// Read one frame of any kind, the size limit and the timeout are enforced here
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    /// use http_body_util::BodyExt;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> FibraResult<()> {
+    ///     let mut trailers = HeaderMap::new();
+    ///     trailers.insert("x-checksum", HeaderValue::from_static("abc"));
+    ///
+    ///     let frames = futures::stream::iter(vec![Ok::<_, FibraError>(hyper::body::Frame::data(bytes::Bytes::from("Hello"))), Ok(hyper::body::Frame::trailers(trailers.clone()))]);
+    ///     let mut body = Body::from(http_body_util::StreamBody::new(frames).boxed());
+    ///
+    ///     assert_eq!(body.try_read_raw().await?, Some(BodyFrame::Data(bytes::Bytes::from("Hello"))));
+    ///     assert_eq!(body.try_read_raw().await?, Some(BodyFrame::Trailers(trailers.clone())));
+    ///     assert_eq!(body.try_read_raw().await?, None);
+    ///     assert_eq!(body.trailers(), Some(&trailers));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn try_read_raw(&mut self) -> FibraResult<Option<BodyFrame>> {
+        loop {
+            let frame = match self.timeout {
+                Some(timeout) => tokio::time::timeout(timeout, self.inner.frame()).await.map_err(|_| Rejection::RequestTimeout)?,
+                None => self.inner.frame().await,
+            };
+
+            let frame = match frame {
+                Some(frame) => frame?,
+                None => return Ok(None),
+            };
+
+            let frame = match frame.into_data() {
+                Ok(data) => data,
+                Err(frame) => match frame.into_trailers() {
+                    Ok(trailers) => {
+                        self.trailers = Some(trailers.clone());
+                        return Ok(Some(BodyFrame::Trailers(trailers)));
+                    }
+                    Err(_) => continue,
+                },
+            };
+
+            self.length += frame.len();
+
+            if let Some(limit) = self.limit {
+                if self.length > limit {
+                    return Err(Rejection::PayloadTooLarge(limit).into());
+                }
+            }
+
+            return Ok(Some(BodyFrame::Data(frame)));
+        }
+    }
+
+    /// The trailers received after the body is read
+    #[inline]
+    pub fn trailers(&self) -> Option<&HeaderMap> {
+        self.trailers.as_ref()
     }
 }
 
 impl From<Body> for BoxBody {
     #[inline]
     fn from(value: Body) -> Self {
-        value.0
+        value.inner
     }
 }
 
 impl From<()> for Body {
     #[inline]
     fn from(_: ()) -> Self {
-        Self(BoxBody::default())
+        BoxBody::default().into()
     }
 }
 
 impl From<BoxBody> for Body {
     #[inline]
     fn from(value: BoxBody) -> Self {
-        Self(value)
+        Self { inner: value, limit: None, timeout: None, length: 0, trailers: None }
     }
 }
 
 impl From<Bytes> for Body {
     #[inline]
     fn from(value: Bytes) -> Self {
-        Self(Full::new(value).map_err(|err| match err {}).boxed())
+        Full::new(value).map_err(|err| match err {}).boxed().into()
     }
 }
 
 impl From<&'static str> for Body {
     #[inline]
     fn from(value: &'static str) -> Self {
-        Self(Full::from(value).map_err(|err| match err {}).boxed())
+        Full::from(value).map_err(|err| match err {}).boxed().into()
     }
 }
 
 impl From<String> for Body {
     #[inline]
     fn from(value: String) -> Self {
-        Self(Full::from(value).map_err(|err| match err {}).boxed())
+        Full::from(value).map_err(|err| match err {}).boxed().into()
     }
 }
 
 impl From<&'static [u8]> for Body {
     #[inline]
     fn from(value: &'static [u8]) -> Self {
-        Self(Full::from(value).map_err(|err| match err {}).boxed())
+        Full::from(value).map_err(|err| match err {}).boxed().into()
     }
 }
 
 impl From<Vec<u8>> for Body {
     #[inline]
     fn from(value: Vec<u8>) -> Self {
-        Self(Full::from(value).map_err(|err| match err {}).boxed())
+        Full::from(value).map_err(|err| match err {}).boxed().into()
     }
 }
\ No newline at end of file
@@ -0,0 +1,2 @@
+//! HTTP Cookie
+pub use ::cookie::{Cookie, CookieBuilder, CookieJar, Expiration, Key, SameSite};
\ No newline at end of file
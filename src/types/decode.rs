@@ -0,0 +1,353 @@
+//! Decode key-value pairs into typed values
+use crate::types::*;
+use serde::de::{self, IntoDeserializer, Visitor};
+
+/// Decode Error, the field is the bracketed path of the bad value
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DecodeError {
+    field: Option<String>,
+    message: String,
+}
+
+impl DecodeError {
+    /// The path of the bad field, e.g. filter[status]
+    #[inline]
+    pub fn field(&self) -> Option<&str> {
+        self.field.as_deref()
+    }
+
+    /// The error message without the field
+    #[inline]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Prepend the parent key to the field path
+    fn within(mut self, key: &str) -> Self {
+        self.field = Some(match self.field.take() {
+            Some(field) => match field.split_once('[') {
+                Some((head, tail)) => format!("{}[{}][{}", key, head, tail),
+                None => format!("{}[{}]", key, field),
+            },
+            None => key.to_string(),
+        });
+        self
+    }
+}
+
+impl Display for DecodeError {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.field {
+            Some(field) => write!(f, "{}: {}", field, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl de::Error for DecodeError {
+    #[inline]
+    fn custom<T: Display>(msg: T) -> Self {
+        Self { field: None, message: msg.to_string() }
+    }
+
+    #[inline]
+    fn missing_field(field: &'static str) -> Self {
+        Self { field: Some(field.to_string()), message: "missing field".to_string() }
+    }
+}
+
+/// The max number of segments in a bracketed key, deeper keys are rejected
+const MAX_DEPTH: usize = 32;
+
+/// A decoded node, repeated keys are collected into one slot
+#[derive(Debug, Clone)]
+pub(crate) enum Node {
+    Leaf(String),
+    Branch(IndexMap<String, Vec<Node>>),
+}
+
+/// Decode pairs into T, repeated keys become sequences, bracketed keys like filter[status]
+/// become nested maps and keys like tag[] are appended to the same sequence, keys nested deeper
+/// than MAX_DEPTH are rejected
+pub(crate) fn decode_pairs<'a, T, I>(pairs: I) -> Result<T, DecodeError>
+where
+    T: de::DeserializeOwned,
+    I: IntoIterator<Item = (Cow<'a, str>, Cow<'a, str>)>,
+{
+    let mut root: IndexMap<String, Vec<Node>> = IndexMap::new();
+
+    for (key, val) in pairs {
+        let mut segments = key_segments(&key)?.into_iter().peekable();
+        let mut map = &mut root;
+
+        while let Some(segment) = segments.next() {
+            let slot = map.entry(segment.to_string()).or_default();
+
+            if segments.peek().is_none() {
+                slot.push(Node::Leaf(val.into_owned()));
+                break;
+            }
+
+            if !matches!(slot.last(), Some(Node::Branch(_))) {
+                slot.push(Node::Branch(IndexMap::new()));
+            }
+
+            map = match slot.last_mut() {
+                Some(Node::Branch(map)) => map,
+                _ => unreachable!(),
+            };
+        }
+    }
+
+    T::deserialize(Slot(vec![Node::Branch(root)]))
+}
+
+/// Decode a single value into T
+pub(crate) fn decode_value<T: de::DeserializeOwned>(val: &str) -> Result<T, DecodeError> {
+    T::deserialize(Slot(vec![Node::Leaf(val.to_string())]))
+}
+
+/// Split a key into segments, a[b][c] becomes [a, b, c], empty brackets are dropped, keys
+/// with unbalanced brackets are used as is
+fn key_segments(key: &str) -> Result<Vec<&str>, DecodeError> {
+    let (head, mut rest) = match key.find('[') {
+        Some(0) | None => return Ok(vec![key]),
+        Some(pos) => (&key[..pos], &key[pos..]),
+    };
+
+    let mut segments = vec![head];
+
+    while !rest.is_empty() {
+        let close = match rest.strip_prefix('[').and_then(|tail| tail.find(']')) {
+            Some(close) => close,
+            None => return Ok(vec![key]),
+        };
+
+        if close > 0 {
+            segments.push(&rest[1..=close]);
+        }
+
+        if segments.len() > MAX_DEPTH {
+            return Err(DecodeError { field: Some(head.to_string()), message: format!("key nested deeper than {} levels", MAX_DEPTH) });
+        }
+
+        rest = &rest[close + 2..];
+    }
+
+    Ok(segments)
+}
+
+/// The deserializer of one slot
+struct Slot(Vec<Node>);
+
+impl Slot {
+    /// Take the last leaf
+    fn leaf(self) -> Result<String, DecodeError> {
+        match self.0.into_iter().last() {
+            Some(Node::Leaf(val)) => Ok(val),
+            Some(Node::Branch(_)) => Err(de::Error::custom("expected a value, found a map")),
+            None => Err(de::Error::custom("missing value")),
+        }
+    }
+
+    /// Parse the leaf into a primitive
+    fn parse<T: std::str::FromStr>(self, name: &str) -> Result<T, DecodeError> {
+        let val = self.leaf()?;
+        val.parse().map_err(|_| de::Error::custom(format!("invalid {} value: {:?}", name, val)))
+    }
+}
+
+macro_rules! deserialize_primitive {
+    ($($method:ident => $visit:ident : $ty:ty),* $(,)?) => {
+        $(
+            #[inline]
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                visitor.$visit(self.parse::<$ty>(stringify!($ty))?)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Slot {
+    type Error = DecodeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0.len() {
+            0 => visitor.visit_unit(),
+            1 => match self.0.into_iter().next() {
+                Some(Node::Leaf(val)) => visitor.visit_string(val),
+                Some(Node::Branch(map)) => visitor.visit_map(Entries::new(map)),
+                None => unreachable!(),
+            },
+            _ => visitor.visit_seq(Items(self.0.into_iter(), 0)),
+        }
+    }
+
+    deserialize_primitive! {
+        deserialize_bool => visit_bool: bool,
+        deserialize_i8 => visit_i8: i8,
+        deserialize_i16 => visit_i16: i16,
+        deserialize_i32 => visit_i32: i32,
+        deserialize_i64 => visit_i64: i64,
+        deserialize_i128 => visit_i128: i128,
+        deserialize_u8 => visit_u8: u8,
+        deserialize_u16 => visit_u16: u16,
+        deserialize_u32 => visit_u32: u32,
+        deserialize_u64 => visit_u64: u64,
+        deserialize_u128 => visit_u128: u128,
+        deserialize_f32 => visit_f32: f32,
+        deserialize_f64 => visit_f64: f64,
+        deserialize_char => visit_char: char,
+    }
+
+    #[inline]
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_string(self.leaf()?)
+    }
+
+    #[inline]
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_string(self.leaf()?)
+    }
+
+    #[inline]
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_byte_buf(self.leaf()?.into_bytes())
+    }
+
+    #[inline]
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_byte_buf(self.leaf()?.into_bytes())
+    }
+
+    /// Empty values are treated as None
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0.last() {
+            None => visitor.visit_none(),
+            Some(Node::Leaf(val)) if val.is_empty() => visitor.visit_none(),
+            Some(_) => visitor.visit_some(self),
+        }
+    }
+
+    #[inline]
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+
+    #[inline]
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+
+    #[inline]
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_seq(Items(self.0.into_iter(), 0))
+    }
+
+    #[inline]
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    #[inline]
+    fn deserialize_tuple_struct<V: Visitor<'de>>(self, _name: &'static str, _len: usize, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0.into_iter().last() {
+            Some(Node::Branch(map)) => visitor.visit_map(Entries::new(map)),
+            Some(Node::Leaf(val)) => Err(de::Error::custom(format!("expected a map, found {:?}", val))),
+            None => visitor.visit_map(Entries::new(IndexMap::new())),
+        }
+    }
+
+    #[inline]
+    fn deserialize_struct<V: Visitor<'de>>(self, _name: &'static str, _fields: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_map(visitor)
+    }
+
+    #[inline]
+    fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_enum(self.leaf()?.into_deserializer())
+    }
+
+    #[inline]
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_string(visitor)
+    }
+
+    #[inline]
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+}
+
+/// Sequence access
+struct Items(std::vec::IntoIter<Node>, usize);
+
+impl<'de> de::SeqAccess<'de> for Items {
+    type Error = DecodeError;
+
+    #[inline]
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error> {
+        match self.0.next() {
+            Some(node) => {
+                self.1 += 1;
+                seed.deserialize(Slot(vec![node])).map(Some).map_err(|err| err.within(&(self.1 - 1).to_string()))
+            }
+            None => Ok(None),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+/// Map access
+struct Entries {
+    iter: indexmap::map::IntoIter<String, Vec<Node>>,
+    next: Option<(String, Vec<Node>)>,
+}
+
+impl Entries {
+    #[inline]
+    fn new(map: IndexMap<String, Vec<Node>>) -> Self {
+        Self { iter: map.into_iter(), next: None }
+    }
+}
+
+impl<'de> de::MapAccess<'de> for Entries {
+    type Error = DecodeError;
+
+    #[inline]
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error> {
+        match self.iter.next() {
+            Some((key, val)) => {
+                self.next = Some((key.clone(), val));
+                seed.deserialize(key.into_deserializer()).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    #[inline]
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Self::Error> {
+        let (key, val) = self.next.take().unwrap_or_default();
+        seed.deserialize(Slot(val)).map_err(|err| err.within(&key))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
\ No newline at end of file
@@ -20,6 +20,12 @@ pub enum FibraError {
     #[error("{0}")]
     RadixError(#[from] radixmap::RadixError),
 
+    #[error("{0}")]
+    RegexError(#[from] regex::Error),
+
+    #[error("{0}")]
+    GlobError(#[from] glob::PatternError),
+
     #[error("{0}")]
     HyperError(#[from] hyper::Error),
 
@@ -29,15 +35,180 @@ pub enum FibraError {
     #[error("{0}")]
     UriInvalid(#[from] hyper::http::uri::InvalidUri),
 
+    #[error("{0}")]
+    AddrInvalid(#[from] std::net::AddrParseError),
+
+    #[error("{0}")]
+    IpNetInvalid(#[from] ipnet::AddrParseError),
+
+    #[error("{0}")]
+    MimeInvalid(#[from] mime::FromStrError),
+
     #[error("{0}")]
     HeaderNameInvalid(#[from] header::InvalidHeaderName),
 
     #[error("{0}")]
     HeaderValueInvalid(#[from] header::InvalidHeaderValue),
 
+    #[error("{0}")]
+    Rejection(#[from] Rejection),
+
+    #[error("{0}")]
+    WebSocketError(Cow<'static, str>),
+
     #[error("addr not available")]
     AddrNotAvailable,
 }
 
+impl FibraError {
+    /// The status to reply for this error
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// assert_eq!(FibraError::AddrNotAvailable.status(), Status::INTERNAL_SERVER_ERROR);
+    /// assert_eq!(FibraError::from(Rejection::InvalidQuery("page".into())).status(), Status::BAD_REQUEST);
+    /// ```
+    #[inline]
+    pub fn status(&self) -> Status {
+        match self {
+            FibraError::Rejection(rejection) => rejection.status(),
+            _ => Status::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// The response to reply for this error, rejections carry a json body describing the
+    /// failure, other errors only carry the status to avoid leaking internals
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> FibraResult<()> {
+    ///     let err = FibraError::from(Rejection::InvalidJson { path: "user.age".into(), message: "invalid type".into() });
+    ///     let mut res = err.response();
+    ///
+    ///     assert_eq!(res.status_ref(), &Status::BAD_REQUEST);
+    ///     assert_eq!(res.header_ref(header::CONTENT_TYPE).map(|v| v.as_bytes()), Some(mime::APPLICATION_JSON.as_ref().as_bytes()));
+    ///     assert_eq!(res.body_all().await.unwrap_or_default(), r#"{"status":400,"error":"invalid json at user.age: invalid type","path":"user.age"}"#);
+    ///
+    ///     assert_eq!(FibraError::AddrNotAvailable.response().body_all().await.unwrap_or_default(), "");
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn response(&self) -> Response {
+        match self {
+            FibraError::Rejection(rejection) => rejection.response(),
+            _ => self.status().into(),
+        }
+    }
+}
+
+/// Rejections of the request, usually caused by extracting data from an invalid request
+#[derive(Debug, Error)]
+pub enum Rejection {
+    /// Params can't be decoded
+    #[error("invalid param: {0}")]
+    InvalidParam(String),
+
+    /// Query string can't be decoded
+    #[error("invalid query: {0}")]
+    InvalidQuery(String),
+
+    /// Required header is absent
+    #[error("missing header: {0}")]
+    MissingHeader(HeaderName),
+
+    /// Header can't be decoded
+    #[error("invalid header: {0}")]
+    InvalidHeader(HeaderName),
+
+    /// Content type is not the expected one
+    #[error("unsupported media type, expect {0}")]
+    UnsupportedMediaType(Mime),
+
+    /// Body can't be decoded as json, the path is where the failure occurs, e.g. user.tags[0]
+    #[error("invalid json at {path}: {message}")]
+    InvalidJson {
+        /// The json path
+        path: String,
+
+        /// The error message
+        message: String,
+    },
+
+    /// Body can't be decoded as form
+    #[error("invalid form: {0}")]
+    InvalidForm(String),
+
+    /// Required state is not registered on any router
+    #[error("missing state: {0}")]
+    MissingState(&'static str),
+
+    /// Body can't be decoded as multipart
+    #[error("invalid multipart: {0}")]
+    InvalidMultipart(String),
+
+    /// Multipart body has too many parts
+    #[error("too many parts, limit {0}")]
+    TooManyParts(usize),
+
+    /// Body is not received in time
+    #[error("request timeout")]
+    RequestTimeout,
+
+    /// Body exceeds the size limit
+    #[error("payload too large, limit {0} bytes")]
+    PayloadTooLarge(usize),
+
+    /// Upgrade request is malformed
+    #[error("invalid upgrade: {0}")]
+    InvalidUpgrade(String),
+
+    /// WebSocket version is not 13
+    #[error("unsupported websocket version, expect 13")]
+    UnsupportedWebSocketVersion,
+}
+
+impl Rejection {
+    /// The status to reply for this rejection
+    #[inline]
+    pub fn status(&self) -> Status {
+        match self {
+            Rejection::UnsupportedMediaType(_) => Status::UNSUPPORTED_MEDIA_TYPE,
+            Rejection::MissingState(_) => Status::INTERNAL_SERVER_ERROR,
+            Rejection::PayloadTooLarge(_) | Rejection::TooManyParts(_) => Status::PAYLOAD_TOO_LARGE,
+            Rejection::RequestTimeout => Status::REQUEST_TIMEOUT,
+            Rejection::UnsupportedWebSocketVersion => Status::UPGRADE_REQUIRED,
+            _ => Status::BAD_REQUEST,
+        }
+    }
+
+    /// The json response describing this rejection
+    pub fn response(&self) -> Response {
+        let status = self.status();
+        let code = status.as_u16();
+        let error = self.to_string();
+        let mut body: IndexMap<&str, &dyn encoder::json::Encode> = IndexMap::new();
+
+        body.insert("status", &code);
+        body.insert("error", &error);
+
+        if let Rejection::InvalidJson { path, .. } = self {
+            body.insert("path", path);
+        }
+
+        match self {
+            Rejection::UnsupportedWebSocketVersion => Response::new().status(status).header(header::SEC_WEBSOCKET_VERSION, "13").json(body),
+            _ => Response::new().status(status).json(body),
+        }
+    }
+}
+
 /// Custom Result
 pub type FibraResult<T> = Result<T, FibraError>;
\ No newline at end of file
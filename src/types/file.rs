@@ -0,0 +1,422 @@
+//! File Response
+use crate::types::*;
+use std::path::Path;
+use std::io::SeekFrom;
+use std::collections::VecDeque;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncSeekExt;
+
+/// The max count of ranges in one request, larger range sets are ignored and served in full
+const MAX_RANGES: usize = 32;
+
+/// The size of each chunk read from files
+const CHUNK_SIZE: u64 = 64 * 1024;
+
+impl Response {
+    /// Stream a file with its MIME type, Content-Length, Last-Modified and ETag, missing files
+    /// and directories are replied with NOT_FOUND, use `file_for` or `ctx.file` to honor the
+    /// conditional and range headers of the request
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> FibraResult<()> {
+    ///     let path = std::env::temp_dir().join("fibra-file.txt");
+    ///     std::fs::write(&path, "The quick brown fox jumps over the lazy dog.")?;
+    ///
+    ///     let mut res = Response::new().file(&path).await?;
+    ///
+    ///     assert_eq!(res.status_ref(), &Status::OK);
+    ///     assert_eq!(res.header_ref(header::CONTENT_TYPE).map(|v| v.as_bytes()), Some("text/plain".as_bytes()));
+    ///     assert_eq!(res.header_ref(header::CONTENT_LENGTH).map(|v| v.as_bytes()), Some("44".as_bytes()));
+    ///     assert_eq!(res.header_ref(header::ETAG).is_some(), true);
+    ///     assert_eq!(res.header_ref(header::LAST_MODIFIED).is_some(), true);
+    ///     assert_eq!(res.body_all().await.unwrap_or_default(), "The quick brown fox jumps over the lazy dog.");
+    ///
+    ///     assert_eq!(Response::new().file(path.with_extension("none")).await?.status_ref(), &Status::NOT_FOUND);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[inline]
+    pub async fn file(self, path: impl AsRef<Path>) -> FibraResult<Self> {
+        self.file_serve(path.as_ref(), None).await
+    }
+
+    /// Stream a file like `file`, and reply NOT_MODIFIED, PRECONDITION_FAILED, PARTIAL_CONTENT
+    /// or RANGE_NOT_SATISFIABLE according to the request's conditional and range headers
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> FibraResult<()> {
+    ///     let path = std::env::temp_dir().join("fibra-file-for.txt");
+    ///     std::fs::write(&path, "The quick brown fox jumps over the lazy dog.")?;
+    ///
+    ///     let etag = Response::new().file(&path).await?.header_ref(header::ETAG).cloned().ok_or(FibraError::PanicError("no etag".into()))?;
+    ///
+    ///     // conditional get
+    ///     let req = Request::new().header(header::IF_NONE_MATCH, etag.clone());
+    ///     assert_eq!(Response::new().file_for(&path, &req).await?.status_ref(), &Status::NOT_MODIFIED);
+    ///
+    ///     // single range
+    ///     let req = Request::new().header(header::RANGE, "bytes=4-8");
+    ///     let mut res = Response::new().file_for(&path, &req).await?;
+    ///
+    ///     assert_eq!(res.status_ref(), &Status::PARTIAL_CONTENT);
+    ///     assert_eq!(res.header_ref(header::CONTENT_RANGE).map(|v| v.as_bytes()), Some("bytes 4-8/44".as_bytes()));
+    ///     assert_eq!(res.body_all().await.unwrap_or_default(), "quick");
+    ///
+    ///     // suffix range
+    ///     let req = Request::new().header(header::RANGE, "bytes=-4");
+    ///     assert_eq!(Response::new().file_for(&path, &req).await?.body_all().await.unwrap_or_default(), "dog.");
+    ///
+    ///     // multiple ranges
+    ///     let req = Request::new().header(header::RANGE, "bytes=0-2,-4");
+    ///     let mut res = Response::new().file_for(&path, &req).await?;
+    ///     let kind = res.header_ref(header::CONTENT_TYPE).and_then(|v| v.to_str().ok()).unwrap_or_default().to_string();
+    ///     let boundary = kind.trim_start_matches("multipart/byteranges; boundary=");
+    ///     let expect = format!("\r\n--{0}\r\nContent-Type: text/plain\r\nContent-Range: bytes 0-2/44\r\n\r\nThe\
+    ///                           \r\n--{0}\r\nContent-Type: text/plain\r\nContent-Range: bytes 40-43/44\r\n\r\ndog.\
+    ///                           \r\n--{0}--\r\n", boundary);
+    ///
+    ///     assert_eq!(res.status_ref(), &Status::PARTIAL_CONTENT);
+    ///     assert_eq!(res.header_ref(header::CONTENT_LENGTH).map(|v| v.as_bytes()), Some(expect.len().to_string().as_bytes()));
+    ///     assert_eq!(res.body_all().await.unwrap_or_default(), expect);
+    ///
+    ///     // overlapping ranges are merged
+    ///     let req = Request::new().header(header::RANGE, "bytes=0-,0-,4-8,0-");
+    ///     let res = Response::new().file_for(&path, &req).await?;
+    ///
+    ///     assert_eq!(res.header_ref(header::CONTENT_RANGE).map(|v| v.as_bytes()), Some("bytes 0-43/44".as_bytes()));
+    ///
+    ///     // unsatisfiable range
+    ///     let req = Request::new().header(header::RANGE, "bytes=100-");
+    ///     let res = Response::new().file_for(&path, &req).await?;
+    ///
+    ///     assert_eq!(res.status_ref(), &Status::RANGE_NOT_SATISFIABLE);
+    ///     assert_eq!(res.header_ref(header::CONTENT_RANGE).map(|v| v.as_bytes()), Some("bytes */44".as_bytes()));
+    ///
+    ///     // stale if-range
+    ///     let req = Request::new().header(header::RANGE, "bytes=4-8").header(header::IF_RANGE, "\"stale\"");
+    ///     assert_eq!(Response::new().file_for(&path, &req).await?.status_ref(), &Status::OK);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[inline]
+    pub async fn file_for(self, path: impl AsRef<Path>, req: &Request) -> FibraResult<Self> {
+        self.file_serve(path.as_ref(), Some(req)).await
+    }
+
+    /// Open the file and build the response
+    async fn file_serve(self, path: &Path, req: Option<&Request>) -> FibraResult<Self> {
+        let file = match Entity::open(path, None).await? {
+            Some(file) => file,
+            None => return Ok(self.status(Status::NOT_FOUND)),
+        };
+
+        Ok(file.respond(self, req))
+    }
+}
+
+/// A representation to be served with validators, conditional and range requests are honored
+pub(crate) struct Entity {
+    source: Source,
+    len: u64,
+    mime: Mime,
+    etag: String,
+    modified: Option<SystemTime>,
+    encoding: Option<&'static str>,
+}
+
+/// Where the content comes from
+enum Source {
+    File(tokio::fs::File),
+    Memory(Bytes),
+}
+
+/// The parts of a body, either literal bytes or a range of the source
+enum Segment {
+    Bytes(Bytes),
+    Range(u64, u64),
+}
+
+impl Entity {
+    /// Open a regular file, None if it doesn't exist or isn't a file, the MIME is guessed from
+    /// the extension of `mime_of` or the path itself
+    pub(crate) async fn open(path: &Path, mime_of: Option<&Path>) -> FibraResult<Option<Self>> {
+        let file = match tokio::fs::File::open(path).await {
+            Ok(file) => file,
+            Err(err) if matches!(err.kind(), std::io::ErrorKind::NotFound | std::io::ErrorKind::NotADirectory) => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        let meta = file.metadata().await?;
+
+        if !meta.is_file() {
+            return Ok(None);
+        }
+
+        let modified = meta.modified().ok();
+        let stamp = modified.and_then(|v| v.duration_since(UNIX_EPOCH).ok()).map(|v| v.as_nanos()).unwrap_or(0);
+
+        Ok(Some(Self {
+            source: Source::File(file),
+            len: meta.len(),
+            mime: mime_guess::from_path(mime_of.unwrap_or(path)).first_or_octet_stream(),
+            etag: format!("\"{:x}-{:x}\"", stamp, meta.len()),
+            modified,
+            encoding: None,
+        }))
+    }
+
+    /// Wrap the content in memory, the etag must be quoted
+    pub(crate) fn memory(data: Bytes, mime: Mime, etag: String, modified: Option<SystemTime>) -> Self {
+        Self { len: data.len() as u64, source: Source::Memory(data), mime, etag, modified, encoding: None }
+    }
+
+    /// Mark the content as encoded, the etag is changed to distinguish it from other encodings
+    pub(crate) fn encoding(mut self, encoding: &'static str) -> Self {
+        self.etag = format!("{}-{}\"", self.etag.trim_end_matches('"'), encoding);
+        self.encoding = Some(encoding);
+        self
+    }
+
+    /// Build the response by the request's conditional and range headers
+    pub(crate) fn respond(self, res: Response, req: Option<&Request>) -> Response {
+        let mut res = res.header(header::ETAG, value(&self.etag)).header(header::ACCEPT_RANGES, "bytes");
+
+        if let Some(modified) = self.modified {
+            res = res.header(header::LAST_MODIFIED, value(&httpdate::fmt_http_date(modified)));
+        }
+
+        if let Some(encoding) = self.encoding {
+            res = res.header(header::CONTENT_ENCODING, encoding);
+        }
+
+        let req = match req {
+            Some(req) => req,
+            None => return self.full(res),
+        };
+
+        let safe = matches!(*req.method_ref(), Method::GET | Method::HEAD);
+
+        if let Some(status) = self.precondition(req, safe) {
+            return res.status(status);
+        }
+
+        if *req.method_ref() != Method::GET || !self.if_range(req) {
+            return self.full(res);
+        }
+
+        let ranges = match req.header_ref(header::RANGE).and_then(|v| v.to_str().ok()).and_then(|v| parse_ranges(v, self.len)) {
+            Some(ranges) => ranges,
+            None => return self.full(res),
+        };
+
+        match ranges.len() {
+            0 => res.status(Status::RANGE_NOT_SATISFIABLE).header(header::CONTENT_RANGE, value(&format!("bytes */{}", self.len))),
+            1 => {
+                let (start, end) = ranges[0];
+
+                res.status(Status::PARTIAL_CONTENT)
+                    .header(header::CONTENT_TYPE, self.mime.clone())
+                    .header(header::CONTENT_RANGE, value(&format!("bytes {}-{}/{}", start, end, self.len)))
+                    .header(header::CONTENT_LENGTH, value(&(end - start + 1).to_string()))
+                    .stream(self.source.stream(vec![Segment::Range(start, end - start + 1)]))
+            }
+            _ => {
+                let boundary = ulid::Ulid::new().to_string();
+                let mut segments = vec![];
+                let mut length = 0;
+
+                for (start, end) in ranges {
+                    let head = format!("\r\n--{}\r\nContent-Type: {}\r\nContent-Range: bytes {}-{}/{}\r\n\r\n", boundary, self.mime, start, end, self.len);
+                    length += head.len() as u64 + end - start + 1;
+                    segments.push(Segment::Bytes(head.into()));
+                    segments.push(Segment::Range(start, end - start + 1));
+                }
+
+                let tail = format!("\r\n--{}--\r\n", boundary);
+                length += tail.len() as u64;
+                segments.push(Segment::Bytes(tail.into()));
+
+                res.status(Status::PARTIAL_CONTENT)
+                    .header(header::CONTENT_TYPE, value(&format!("multipart/byteranges; boundary={}", boundary)))
+                    .header(header::CONTENT_LENGTH, value(&length.to_string()))
+                    .stream(self.source.stream(segments))
+            }
+        }
+    }
+
+    /// The whole content
+    fn full(self, res: Response) -> Response {
+        res.status(Status::OK)
+            .header(header::CONTENT_TYPE, self.mime.clone())
+            .header(header::CONTENT_LENGTH, value(&self.len.to_string()))
+            .stream(self.source.stream(vec![Segment::Range(0, self.len)]))
+    }
+
+    /// Evaluate the preconditions in the order of RFC 9110, returns the status to reply if any
+    fn precondition(&self, req: &Request, safe: bool) -> Option<Status> {
+        let header = |key: HeaderName| req.header_ref(key).and_then(|v| v.to_str().ok());
+        let modified = self.modified.and_then(|v| v.duration_since(UNIX_EPOCH).ok()).map(|v| v.as_secs());
+        let since = |val: &str| httpdate::parse_http_date(val).ok().and_then(|v| v.duration_since(UNIX_EPOCH).ok()).map(|v| v.as_secs());
+
+        match header(header::IF_MATCH) {
+            Some(tags) if !etag_match(tags, &self.etag, false) => return Some(Status::PRECONDITION_FAILED),
+            Some(_) => {}
+            None => {
+                if let (Some(modified), Some(since)) = (modified, header(header::IF_UNMODIFIED_SINCE).and_then(since)) {
+                    if modified > since {
+                        return Some(Status::PRECONDITION_FAILED);
+                    }
+                }
+            }
+        }
+
+        match header(header::IF_NONE_MATCH) {
+            Some(tags) if etag_match(tags, &self.etag, true) => return Some(if safe { Status::NOT_MODIFIED } else { Status::PRECONDITION_FAILED }),
+            Some(_) => {}
+            None if safe => {
+                if let (Some(modified), Some(since)) = (modified, header(header::IF_MODIFIED_SINCE).and_then(since)) {
+                    if modified <= since {
+                        return Some(Status::NOT_MODIFIED);
+                    }
+                }
+            }
+            None => {}
+        }
+
+        None
+    }
+
+    /// Whether the range should be applied, If-Range must match the current representation
+    fn if_range(&self, req: &Request) -> bool {
+        let val = match req.header_ref(header::IF_RANGE).and_then(|v| v.to_str().ok()) {
+            Some(val) => val.trim(),
+            None => return true,
+        };
+
+        if val.starts_with('"') || val.starts_with("W/") {
+            return etag_match(val, &self.etag, false);
+        }
+
+        // dates are only valid validators when they are exactly the last modified time
+        match (self.modified, httpdate::parse_http_date(val)) {
+            (Some(modified), Ok(date)) => httpdate::fmt_http_date(modified) == httpdate::fmt_http_date(date),
+            _ => false,
+        }
+    }
+}
+
+impl Source {
+    /// Stream the segments
+    fn stream(self, segments: Vec<Segment>) -> impl Stream<Item = FibraResult<Bytes>> + Send + Sync + 'static {
+        futures::stream::try_unfold((self, None, VecDeque::from(segments)), |(mut source, mut pos, mut segments)| async move {
+            loop {
+                let (file, start, len) = match (segments.pop_front(), &mut source) {
+                    (None, _) => return Ok(None),
+                    (Some(Segment::Bytes(bytes)), _) => return Ok(Some((bytes, (source, pos, segments)))),
+                    (Some(Segment::Range(_, 0)), _) => continue,
+                    (Some(Segment::Range(start, len)), Source::Memory(data)) => {
+                        let chunk = data.slice(start as usize..(start + len) as usize);
+                        return Ok(Some((chunk, (source, pos, segments))));
+                    }
+                    (Some(Segment::Range(start, len)), Source::File(file)) => (file, start, len),
+                };
+
+                if pos != Some(start) {
+                    file.seek(SeekFrom::Start(start)).await?;
+                }
+
+                let size = len.min(CHUNK_SIZE);
+                let mut buf = vec![0; size as usize];
+                file.read_exact(&mut buf).await?;
+
+                if len > size {
+                    segments.push_front(Segment::Range(start + size, len - size));
+                }
+
+                pos = Some(start + size);
+
+                return Ok(Some((buf.into(), (source, pos, segments))));
+            }
+        })
+    }
+}
+
+/// Header value of formatted text, the text must be visible ascii
+fn value(text: &str) -> HeaderValue {
+    HeaderValue::try_from(text).unwrap_or_else(|_| HeaderValue::from_static(""))
+}
+
+/// Match an etag list, weak comparison ignores the W/ prefix
+fn etag_match(tags: &str, etag: &str, weak: bool) -> bool {
+    tags.split(',').map(|v| v.trim()).any(|tag| match tag {
+        "*" => true,
+        _ if weak => tag.trim_start_matches("W/") == etag.trim_start_matches("W/"),
+        _ => !tag.starts_with("W/") && !etag.starts_with("W/") && tag == etag,
+    })
+}
+
+/// Parse the Range header, None if it should be ignored, empty if none of the ranges is
+/// satisfiable, otherwise the inclusive ranges sorted with overlapping and adjacent ones merged
+fn parse_ranges(header: &str, len: u64) -> Option<Vec<(u64, u64)>> {
+    let specs = header.trim().strip_prefix("bytes=")?;
+    let mut ranges = vec![];
+
+    for spec in specs.split(',').map(|v| v.trim()).filter(|v| !v.is_empty()) {
+        let (start, end) = spec.split_once('-')?;
+
+        let range = match (start.trim(), end.trim()) {
+            ("", suffix) => match suffix.parse::<u64>().ok()? {
+                0 => None,
+                _ if len == 0 => None,
+                suffix => Some((len.saturating_sub(suffix), len - 1)),
+            },
+            (start, "") => match start.parse::<u64>().ok()? {
+                start if start < len => Some((start, len - 1)),
+                _ => None,
+            },
+            (start, end) => {
+                let (start, end) = (start.parse::<u64>().ok()?, end.parse::<u64>().ok()?);
+
+                if start > end {
+                    return None;
+                }
+
+                match start < len {
+                    true => Some((start, end.min(len - 1))),
+                    false => None,
+                }
+            }
+        };
+
+        ranges.extend(range);
+    }
+
+    // merge repeated ranges so the same bytes are never sent twice
+    ranges.sort_unstable();
+
+    let mut merged: Vec<(u64, u64)> = vec![];
+
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1.saturating_add(1) => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+
+    match merged.len() > MAX_RANGES {
+        true => None,
+        false => Some(merged),
+    }
+}
\ No newline at end of file
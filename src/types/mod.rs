@@ -2,6 +2,7 @@
 
 /// Internal
 pub(crate) use std::any::Any;
+pub(crate) use std::any::TypeId;
 pub(crate) use std::sync::Arc;
 pub(crate) use std::borrow::Cow;
 pub(crate) use std::sync::atomic;
@@ -9,6 +10,7 @@ pub(crate) use std::fmt::Display;
 pub(crate) use std::fmt::Formatter;
 pub(crate) use std::cell::OnceCell;
 pub(crate) use std::future::Future;
+pub(crate) use std::collections::HashMap;
 pub(crate) use std::net::SocketAddr;
 pub(crate) use std::time::SystemTime;
 pub(crate) use std::time::UNIX_EPOCH;
@@ -32,31 +34,49 @@ pub(crate) use tokio::net::TcpListener as AsyncTcpListener;
 mod authority;
 mod body;
 mod connection;
+mod cookie;
+#[cfg(feature = "serde")]
+mod decode;
 mod error;
+mod file;
 pub mod header;
 mod listener;
 mod macros;
 mod method;
+mod network;
 pub mod mime;
+mod multipart;
 mod redirect;
 mod request;
 mod response;
 mod scheme;
+mod sse;
 mod status;
+mod upgrade;
 mod uri;
 mod version;
+mod websocket;
 
 pub use authority::*;
 pub use body::*;
 pub use connection::*;
+pub use cookie::*;
+#[cfg(feature = "serde")]
+pub use decode::*;
 pub use error::*;
+pub(crate) use file::*;
 pub use header::{HeaderMap, HeaderName, HeaderValue, AsHeaderName, IntoHeaderName, IntoHeaderValue};
 pub use listener::*;
 pub use method::*;
+pub use multipart::*;
+pub use network::*;
 pub use redirect::*;
 pub use request::*;
 pub use response::*;
 pub use scheme::*;
+pub use sse::*;
 pub use status::*;
+pub use upgrade::*;
 pub use uri::*;
-pub use version::*;
\ No newline at end of file
+pub use version::*;
+pub use websocket::*;
\ No newline at end of file
@@ -0,0 +1,374 @@
+//! Multipart Form Data
+use crate::types::*;
+use std::path::Path;
+use std::path::PathBuf;
+use tokio::io::AsyncWriteExt;
+
+/// Limits of a multipart body, exceeding any of them is rejected with PAYLOAD_TOO_LARGE
+#[derive(Debug, Clone, Copy)]
+pub struct MultipartLimit {
+    parts: usize,
+    field: u64,
+    total: u64,
+}
+
+impl MultipartLimit {
+    /// Create a new object, 100 parts, 10 MiB per field and 50 MiB in total by default
+    #[inline]
+    pub fn new() -> Self {
+        Self { parts: 100, field: 10 * 1024 * 1024, total: 50 * 1024 * 1024 }
+    }
+
+    /// Set the max count of parts
+    #[inline]
+    pub fn parts(mut self, parts: usize) -> Self {
+        self.parts = parts;
+        self
+    }
+
+    /// Set the max size of one part
+    #[inline]
+    pub fn field(mut self, field: u64) -> Self {
+        self.field = field;
+        self
+    }
+
+    /// Set the max size of the whole body
+    #[inline]
+    pub fn total(mut self, total: u64) -> Self {
+        self.total = total;
+        self
+    }
+}
+
+impl Default for MultipartLimit {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Streaming multipart/form-data parser, fields are yielded one by one and each field's data
+/// is streamed by chunks, so large uploads are never buffered as a whole
+///
+/// # Examples
+///
+/// ```
+/// use fibra::*;
+///
+/// #[tokio::main]
+/// async fn main() -> FibraResult<()> {
+///     let body = "--X\r\nContent-Disposition: form-data; name=\"title\"\r\n\r\nhello\r\n\
+///                 --X\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\nContent-Type: text/plain\r\n\r\nfile content\r\n\
+///                 --X--\r\n";
+///     let mut form = Multipart::new(Body::from(body), "X", MultipartLimit::new());
+///
+///     let field = form.next_field().await?.ok_or(FibraError::PanicError("missing field".into()))?;
+///     assert_eq!((field.name(), field.file_name()), (Some("title"), None));
+///     assert_eq!(field.text().await?, "hello");
+///
+///     let mut field = form.next_field().await?.ok_or(FibraError::PanicError("missing field".into()))?;
+///     assert_eq!((field.name(), field.file_name(), field.content_type()), (Some("file"), Some("a.txt"), Some(&mime::TEXT_PLAIN)));
+///     assert_eq!(field.chunk().await?, Some(bytes::Bytes::from("file content")));
+///     assert_eq!(field.chunk().await?, None);
+///
+///     drop(field);
+///
+///     assert_eq!(form.next_field().await?.is_none(), true);
+///
+///     Ok(())
+/// }
+/// ```
+pub struct Multipart {
+    inner: multer::Multipart<'static>,
+    limit: MultipartLimit,
+    count: usize,
+}
+
+impl Multipart {
+    /// Create a parser over the body with the boundary
+    pub fn new(body: Body, boundary: impl Into<String>, limit: MultipartLimit) -> Self {
+        let stream = futures::stream::unfold(body, |mut body| async move {
+            body.try_read_frame().await.transpose().map(|bytes| (bytes, body))
+        });
+
+        let constraints = multer::Constraints::new().size_limit(multer::SizeLimit::new().per_field(limit.field).whole_stream(limit.total));
+
+        Self { inner: multer::Multipart::with_constraints(stream, boundary, constraints), limit, count: 0 }
+    }
+
+    /// Yield the next field, the previous field must be dropped before calling this
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> FibraResult<()> {
+    ///     let body = "--X\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\n1\r\n\
+    ///                 --X\r\nContent-Disposition: form-data; name=\"b\"\r\n\r\n2\r\n\
+    ///                 --X--\r\n";
+    ///
+    ///     let mut form = Multipart::new(Body::from(body), "X", MultipartLimit::new().parts(1));
+    ///
+    ///     assert_eq!(form.next_field().await?.is_some(), true);
+    ///     assert_eq!(form.next_field().await.err().map(|e| e.status()), Some(Status::PAYLOAD_TOO_LARGE));
+    ///
+    ///     let mut form = Multipart::new(Body::from(body), "X", MultipartLimit::new().field(0));
+    ///     let field = form.next_field().await?.ok_or(FibraError::PanicError("missing field".into()))?;
+    ///
+    ///     assert_eq!(field.bytes().await.err().map(|e| e.status()), Some(Status::PAYLOAD_TOO_LARGE));
+    ///
+    ///     let mut form = Multipart::new(Body::from("--Y\r\n"), "X", MultipartLimit::new());
+    ///
+    ///     assert_eq!(form.next_field().await.err().map(|e| e.status()), Some(Status::BAD_REQUEST));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn next_field(&mut self) -> FibraResult<Option<MultipartField>> {
+        let field = match self.inner.next_field().await.map_err(reject)? {
+            Some(field) => field,
+            None => return Ok(None),
+        };
+
+        self.count += 1;
+
+        if self.count > self.limit.parts {
+            return Err(Rejection::TooManyParts(self.limit.parts).into());
+        }
+
+        Ok(Some(MultipartField { inner: field }))
+    }
+
+    /// Consume all fields, file parts are written into temp files under the dir and text parts
+    /// are collected into a multi-value map, temp files are removed on drop unless persisted
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> FibraResult<()> {
+    ///     let body = "--X\r\nContent-Disposition: form-data; name=\"title\"\r\n\r\nhello\r\n\
+    ///                 --X\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\nContent-Type: text/plain\r\n\r\nfile content\r\n\
+    ///                 --X--\r\n";
+    ///
+    ///     let spooled = Multipart::new(Body::from(body), "X", MultipartLimit::new()).spool(std::env::temp_dir()).await?;
+    ///     let file = &spooled.files()[0];
+    ///
+    ///     assert_eq!(spooled.fields()["title"], vec!["hello"]);
+    ///     assert_eq!((file.name(), file.file_name(), file.size()), ("file", Some("a.txt"), 12));
+    ///     assert_eq!(std::fs::read_to_string(file.path())?, "file content");
+    ///
+    ///     let path = file.path().to_path_buf();
+    ///     drop(spooled);
+    ///
+    ///     assert_eq!(path.exists(), false);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn spool(mut self, dir: impl AsRef<Path>) -> FibraResult<Spooled> {
+        let mut spooled = Spooled { fields: IndexMap::new(), files: vec![] };
+
+        while let Some(mut field) = self.next_field().await? {
+            let name = field.name().unwrap_or_default().to_string();
+
+            if field.file_name().is_none() {
+                spooled.fields.entry(name).or_default().push(field.text().await?);
+                continue;
+            }
+
+            let temp = tempfile::Builder::new().prefix("fibra-").tempfile_in(dir.as_ref())?;
+            let (file, path) = temp.into_parts();
+            let mut file = tokio::fs::File::from_std(file);
+            let mut size = 0;
+
+            while let Some(chunk) = field.chunk().await? {
+                size += chunk.len() as u64;
+                file.write_all(&chunk).await?;
+            }
+
+            file.flush().await?;
+
+            spooled.files.push(SpooledFile {
+                name,
+                file_name: field.file_name().map(|v| v.to_string()),
+                content_type: field.content_type().cloned(),
+                size,
+                path,
+            });
+        }
+
+        Ok(spooled)
+    }
+}
+
+/// One part of the multipart body
+pub struct MultipartField {
+    inner: multer::Field<'static>,
+}
+
+impl MultipartField {
+    /// The field name
+    #[inline]
+    pub fn name(&self) -> Option<&str> {
+        self.inner.name()
+    }
+
+    /// The file name if it's a file part
+    #[inline]
+    pub fn file_name(&self) -> Option<&str> {
+        self.inner.file_name()
+    }
+
+    /// The content type of the part
+    #[inline]
+    pub fn content_type(&self) -> Option<&Mime> {
+        self.inner.content_type()
+    }
+
+    /// The headers of the part
+    #[inline]
+    pub fn headers(&self) -> &HeaderMap {
+        self.inner.headers()
+    }
+
+    /// The index of the part, begins from 0
+    #[inline]
+    pub fn index(&self) -> usize {
+        self.inner.index()
+    }
+
+    /// Read one chunk of the part, None if finished
+    #[inline]
+    pub async fn chunk(&mut self) -> FibraResult<Option<Bytes>> {
+        self.inner.chunk().await.map_err(reject)
+    }
+
+    /// Read the whole part
+    #[inline]
+    pub async fn bytes(self) -> FibraResult<Bytes> {
+        self.inner.bytes().await.map_err(reject)
+    }
+
+    /// Read the whole part as text, the charset of the content type is honored
+    #[inline]
+    pub async fn text(self) -> FibraResult<String> {
+        self.inner.text().await.map_err(reject)
+    }
+}
+
+/// The result of spooling a multipart body
+#[derive(Debug)]
+pub struct Spooled {
+    fields: IndexMap<String, Vec<String>>,
+    files: Vec<SpooledFile>,
+}
+
+impl Spooled {
+    /// The text parts
+    #[inline]
+    pub fn fields(&self) -> &IndexMap<String, Vec<String>> {
+        &self.fields
+    }
+
+    /// The file parts
+    #[inline]
+    pub fn files(&self) -> &Vec<SpooledFile> {
+        &self.files
+    }
+
+    /// Take the file parts
+    #[inline]
+    pub fn into_files(self) -> Vec<SpooledFile> {
+        self.files
+    }
+}
+
+/// A file part written into a temp file, the file is removed on drop unless persisted
+#[derive(Debug)]
+pub struct SpooledFile {
+    name: String,
+    file_name: Option<String>,
+    content_type: Option<Mime>,
+    size: u64,
+    path: tempfile::TempPath,
+}
+
+impl SpooledFile {
+    /// The field name
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The file name sent by the client, don't use it as a path without sanitizing
+    #[inline]
+    pub fn file_name(&self) -> Option<&str> {
+        self.file_name.as_deref()
+    }
+
+    /// The content type sent by the client
+    #[inline]
+    pub fn content_type(&self) -> Option<&Mime> {
+        self.content_type.as_ref()
+    }
+
+    /// The file size
+    #[inline]
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// The temp file path
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Move the temp file to a permanent path
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> FibraResult<()> {
+    ///     let body = "--X\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\n\r\nfile content\r\n--X--\r\n";
+    ///     let spooled = Multipart::new(Body::from(body), "X", MultipartLimit::new()).spool(std::env::temp_dir()).await?;
+    ///     let target = std::env::temp_dir().join("fibra-persist.txt");
+    ///
+    ///     for file in spooled.into_files() {
+    ///         file.persist(&target)?;
+    ///     }
+    ///
+    ///     assert_eq!(std::fs::read_to_string(&target)?, "file content");
+    ///
+    ///     std::fs::remove_file(&target)?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[inline]
+    pub fn persist(self, to: impl AsRef<Path>) -> FibraResult<PathBuf> {
+        self.path.persist(to.as_ref()).map_err(|err| err.error)?;
+        Ok(to.as_ref().to_path_buf())
+    }
+}
+
+/// Map parser errors to rejections
+fn reject(err: multer::Error) -> FibraError {
+    match err {
+        multer::Error::FieldSizeExceeded { limit, .. } | multer::Error::StreamSizeExceeded { limit } => Rejection::PayloadTooLarge(limit as usize).into(),
+        multer::Error::StreamReadFailed(err) => match err.downcast::<FibraError>() {
+            Ok(err) => *err,
+            Err(err) => Rejection::InvalidMultipart(err.to_string()).into(),
+        },
+        err => Rejection::InvalidMultipart(err.to_string()).into(),
+    }
+}
\ No newline at end of file
@@ -0,0 +1,2 @@
+//! Network Ranges
+pub use ipnet::{IpNet, Ipv4Net, Ipv6Net};
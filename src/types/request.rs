@@ -32,6 +32,9 @@ pub struct Request {
 
     /// The stream body of this request
     body: Body,
+
+    /// The extensions of this request, e.g. the pending upgrade of the connection
+    extensions: hyper::http::Extensions,
 }
 
 impl Request {
@@ -367,6 +370,34 @@ impl Request {
         self.body_mut().read_all().await
     }
 
+    /// Get the trailers, they are only available after the body is consumed
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    /// use http_body_util::BodyExt;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> FibraResult<()> {
+    ///     let mut trailers = HeaderMap::new();
+    ///     trailers.insert("x-checksum", HeaderValue::from_static("abc"));
+    ///
+    ///     let frames = futures::stream::iter(vec![Ok::<_, FibraError>(hyper::body::Frame::data(bytes::Bytes::from("Hello"))), Ok(hyper::body::Frame::trailers(trailers.clone()))]);
+    ///     let mut req = Request::new().body(http_body_util::StreamBody::new(frames).boxed());
+    ///
+    ///     assert_eq!(req.trailers(), None);
+    ///     assert_eq!(req.body_all().await.unwrap_or_default(), "Hello");
+    ///     assert_eq!(req.trailers(), Some(&trailers));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[inline]
+    pub fn trailers(&self) -> Option<&HeaderMap> {
+        self.body.trailers()
+    }
+
     /// Set a new body
     ///
     /// # Examples
@@ -385,6 +416,37 @@ impl Request {
         self.body = val.into();
         self
     }
+
+    /// Get the extensions
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// assert_eq!(Request::new().extensions_ref().is_empty(), true);
+    /// ```
+    #[inline]
+    pub fn extensions_ref(&self) -> &hyper::http::Extensions {
+        &self.extensions
+    }
+
+    /// Get/Set the extensions
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let mut req = Request::new();
+    /// req.extensions_mut().insert(5u8);
+    ///
+    /// assert_eq!(req.extensions_ref().get::<u8>(), Some(&5));
+    /// ```
+    #[inline]
+    pub fn extensions_mut(&mut self) -> &mut hyper::http::Extensions {
+        &mut self.extensions
+    }
 }
 
 impl Request {
@@ -598,6 +660,115 @@ impl Request {
     pub fn href(&self) -> String {
         self.uri.to_string()
     }
+
+    /// Get the content type
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// assert_eq!(Request::new().content_type(), None);
+    /// assert_eq!(Request::new().header(header::CONTENT_TYPE, "application/json").content_type(), Some(mime::APPLICATION_JSON));
+    /// assert_eq!(Request::new().header(header::CONTENT_TYPE, "text/plain; charset=utf-8").content_type(), Some(mime::TEXT_PLAIN_UTF_8));
+    /// ```
+    #[inline]
+    pub fn content_type(&self) -> Option<Mime> {
+        self.header_ref(header::CONTENT_TYPE)?.to_str().ok()?.parse().ok()
+    }
+
+    /// Get the quality value of a mime type according to the Accept header, the most specific
+    /// media range wins, all types are acceptable if the header is absent
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let req = Request::new().header(header::ACCEPT, "text/*;q=0.5, text/html, application/json;q=0, */*;q=0.1");
+    ///
+    /// assert_eq!(req.accept_quality(&mime::TEXT_HTML), 1.0);
+    /// assert_eq!(req.accept_quality(&mime::TEXT_PLAIN), 0.5);
+    /// assert_eq!(req.accept_quality(&mime::APPLICATION_JSON), 0.0);
+    /// assert_eq!(req.accept_quality(&mime::IMAGE_PNG), 0.1);
+    /// assert_eq!(Request::new().accept_quality(&mime::IMAGE_PNG), 1.0);
+    /// ```
+    pub fn accept_quality(&self, mime: &Mime) -> f32 {
+        let mut found = false;
+        let mut best = None;
+
+        for value in self.headers.get_all(header::ACCEPT) {
+            let value = match value.to_str() {
+                Ok(value) => value,
+                Err(_) => continue,
+            };
+
+            for range in value.split(',').filter_map(|v| v.trim().parse::<Mime>().ok()) {
+                found = true;
+
+                let level = match media_match(&range, mime) {
+                    Some(level) => level,
+                    None => continue,
+                };
+
+                let quality = range.get_param("q").and_then(|q| q.as_str().parse::<f32>().ok()).unwrap_or(1.0).clamp(0.0, 1.0);
+
+                match best {
+                    Some((old, _)) if old >= level => {}
+                    _ => best = Some((level, quality)),
+                }
+            }
+        }
+
+        match best {
+            Some((_, quality)) => quality,
+            None if found => 0.0,
+            None => 1.0,
+        }
+    }
+
+    /// Check if a mime type is acceptable to the client
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// fn main() -> FibraResult<()> {
+    ///     let req = Request::new().header(header::ACCEPT, "application/vnd.acme.v2+json");
+    ///
+    ///     assert_eq!(req.accepts(&"application/vnd.acme.v2+json".parse()?), true);
+    ///     assert_eq!(req.accepts(&"application/vnd.acme.v1+json".parse()?), false);
+    ///     assert_eq!(req.accepts(&mime::APPLICATION_JSON), false);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[inline]
+    pub fn accepts(&self, mime: &Mime) -> bool {
+        self.accept_quality(mime) > 0.0
+    }
+}
+
+/// Check if a media range matches a mime type, return the precedence of the range
+fn media_match(range: &Mime, mime: &Mime) -> Option<usize> {
+    let level = match (range.type_() == mime::STAR, range.subtype() == mime::STAR) {
+        (true, true) => 0,
+        (false, true) if range.type_() == mime.type_() => 1,
+        (false, false) if range.essence_str() == mime.essence_str() => 2,
+        _ => return None,
+    };
+
+    let mut params = 0;
+
+    for (key, val) in range.params().filter(|(key, _)| key != &"q") {
+        match mime.get_param(key) {
+            Some(v) if v.as_str().eq_ignore_ascii_case(val.as_str()) => params += 1,
+            _ => return None,
+        }
+    }
+
+    Some(level * 100 + params)
 }
 
 impl Default for Request {
@@ -610,6 +781,7 @@ impl Default for Request {
             version: Default::default(),
             headers: Default::default(),
             body: Default::default(),
+            extensions: Default::default(),
         }
     }
 }
@@ -627,6 +799,7 @@ impl From<hyper::Request<hyper::body::Incoming>> for Request {
             version: head.version,
             headers: head.headers,
             body: body.map_err(|err| err.into()).boxed().into(),
+            extensions: head.extensions,
         }
     }
 }
@@ -642,6 +815,7 @@ impl From<hyper::Request<Body>> for Request {
             version: head.version,
             headers: head.headers,
             body,
+            extensions: head.extensions,
         }
     }
 }
@@ -656,6 +830,7 @@ impl From<hyper::http::request::Parts> for Request {
             version: head.version,
             headers: head.headers,
             body: Default::default(),
+            extensions: head.extensions,
         }
     }
 }
\ No newline at end of file
@@ -218,6 +218,78 @@ impl Response {
         self
     }
 
+    /// Add a Set-Cookie header, multiple cookies can be added
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let cookie = Cookie::build(("uid", "12345"))
+    ///     .path("/")
+    ///     .domain("localip.cc")
+    ///     .max_age(cookie::time::Duration::hours(1))
+    ///     .secure(true)
+    ///     .http_only(true)
+    ///     .same_site(SameSite::Lax)
+    ///     .partitioned(true);
+    ///
+    /// let res = Response::new().cookie(cookie).cookie(Cookie::new("theme", "dark"));
+    /// let all = res.headers_ref().get_all(header::SET_COOKIE).iter().map(|v| v.to_str().unwrap_or("")).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(all, vec!["uid=12345; HttpOnly; SameSite=Lax; Partitioned; Secure; Path=/; Domain=localip.cc; Max-Age=3600", "theme=dark"]);
+    /// ```
+    #[inline]
+    pub fn cookie(mut self, cookie: impl Into<Cookie<'static>>) -> Self {
+        if let Ok(val) = HeaderValue::try_from(cookie.into().to_string()) {
+            self.headers.append(header::SET_COOKIE, val);
+        }
+
+        self
+    }
+
+    /// Ask the client to remove a cookie, the path is "/" if not specified, the path and domain
+    /// must match the ones used when setting it
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// let res = Response::new().remove_cookie("uid").remove_cookie(Cookie::build("theme").path("/app"));
+    /// let all = res.headers_ref().get_all(header::SET_COOKIE).iter().map(|v| v.to_str().unwrap_or("")).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(all[0].starts_with("uid=; Path=/; Max-Age=0; Expires="), true);
+    /// assert_eq!(all[1].starts_with("theme=; Path=/app; Max-Age=0; Expires="), true);
+    /// ```
+    #[inline]
+    pub fn remove_cookie(self, cookie: impl Into<Cookie<'static>>) -> Self {
+        let mut cookie = cookie.into();
+
+        if cookie.path().is_none() {
+            cookie.set_path("/");
+        }
+
+        cookie.make_removal();
+        self.cookie(cookie)
+    }
+
+    /// Add a signed cookie, the value is readable but tamper-proof, see Context::signed_cookie
+    #[inline]
+    pub fn signed_cookie(self, cookie: impl Into<Cookie<'static>>, key: &Key) -> Self {
+        let mut jar = CookieJar::new();
+        jar.signed_mut(key).add(cookie.into());
+        jar.delta().cloned().fold(self, |res, cookie| res.cookie(cookie))
+    }
+
+    /// Add an encrypted cookie, the value is unreadable and tamper-proof, see Context::private_cookie
+    #[inline]
+    pub fn private_cookie(self, cookie: impl Into<Cookie<'static>>, key: &Key) -> Self {
+        let mut jar = CookieJar::new();
+        jar.private_mut(key).add(cookie.into());
+        jar.delta().cloned().fold(self, |res, cookie| res.cookie(cookie))
+    }
+
     /// Get the http body
     ///
     /// # Examples
@@ -463,6 +535,81 @@ impl Response {
 
         self.body(StreamBody::new(val.map_ok(Frame::data)).boxed())
     }
+
+    /// Set a stream body of frames, a trailers frame can be sent at the end for data only known
+    /// after streaming like checksums or grpc status, the Trailer header should declare them
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> FibraResult<()> {
+    ///     let mut trailers = HeaderMap::new();
+    ///     trailers.insert("grpc-status", HeaderValue::from_static("0"));
+    ///
+    ///     let frames = futures::stream::iter(vec![Ok(BodyFrame::Data("Hello".into())), Ok(BodyFrame::Trailers(trailers.clone()))]);
+    ///     let mut res = Response::new().header(header::TRAILER, "grpc-status").stream_frames(frames);
+    ///
+    ///     assert_eq!(res.body_mut().try_read_all().await?, "Hello");
+    ///     assert_eq!(res.body_mut().trailers(), Some(&trailers));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[inline]
+    pub fn stream_frames<S>(self, val: S) -> Self
+    where
+        S: Stream<Item = FibraResult<BodyFrame>> + Send + Sync + 'static,
+    {
+        use hyper::body::Frame;
+        use futures_util::TryStreamExt;
+        use http_body_util::{BodyExt, StreamBody};
+
+        self.body(StreamBody::new(val.map_ok(|frame| match frame {
+            BodyFrame::Data(data) => Frame::data(data),
+            BodyFrame::Trailers(trailers) => Frame::trailers(trailers),
+        })).boxed())
+    }
+
+    /// Append trailers after the current body, the names are declared in the Trailer header
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> FibraResult<()> {
+    ///     let mut trailers = HeaderMap::new();
+    ///     trailers.insert("x-checksum", HeaderValue::from_static("abc"));
+    ///     trailers.insert("x-count", HeaderValue::from_static("1"));
+    ///
+    ///     let mut res = Response::new().body("Hello World!").trailers(trailers.clone());
+    ///
+    ///     assert_eq!(res.header_ref(header::TRAILER).map(|v| v.as_bytes()), Some("x-checksum, x-count".as_bytes()));
+    ///     assert_eq!(res.body_mut().try_read_all().await?, "Hello World!");
+    ///     assert_eq!(res.body_mut().trailers(), Some(&trailers));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn trailers(mut self, trailers: HeaderMap) -> Self {
+        use hyper::body::Frame;
+        use http_body_util::{BodyExt, BodyStream, StreamBody};
+
+        let names = trailers.keys().map(|k| k.as_str()).collect::<Vec<_>>().join(", ");
+
+        if let Ok(names) = HeaderValue::try_from(names) {
+            self.headers.insert(header::TRAILER, names);
+        }
+
+        let body = BoxBody::from(std::mem::take(&mut self.body));
+        let frames = futures_util::StreamExt::chain(BodyStream::new(body), futures::stream::once(async move { Ok(Frame::trailers(trailers)) }));
+
+        self.body(StreamBody::new(frames).boxed())
+    }
 }
 
 /// Conversion
@@ -788,4 +935,115 @@ impl From<Response> for hyper::Response<BoxBody> {
         *res.headers_mut() = value.headers;
         res
     }
+}
+
+/// Types that can be returned from handlers, errors are passed to the Catcher
+///
+/// # Examples
+///
+/// ```
+/// use fibra::*;
+///
+/// struct Created(&'static str);
+///
+/// impl IntoResponse for Created {
+///     fn into_response(self) -> FibraResult<Response> {
+///         Ok(Response::new().status(Status::CREATED).header(header::LOCATION, self.0))
+///     }
+/// }
+///
+/// #[tokio::main]
+/// async fn main() -> FibraResult<()> {
+///     let mut res = Created("/user/12345").into_response()?;
+///
+///     assert_eq!(res.status_ref(), &Status::CREATED);
+///     assert_eq!(res.header_ref(header::LOCATION).map(|v| v.as_bytes()), Some("/user/12345".as_bytes()));
+///
+///     assert_eq!(Some("Hello").into_response()?.body_all().await.unwrap_or_default(), "Hello");
+///     assert_eq!(None::<String>.into_response()?.status_ref(), &Status::NOT_FOUND);
+///     assert_eq!(Err::<String, _>(Status::FORBIDDEN).into_response()?.status_ref(), &Status::FORBIDDEN);
+///     assert_eq!(Err::<String, _>(FibraError::PanicError("oops".into())).into_response().is_err(), true);
+///
+///     Ok(())
+/// }
+/// ```
+pub trait IntoResponse {
+    /// Convert self into a response
+    fn into_response(self) -> FibraResult<Response>;
+}
+
+macro_rules! impl_into_response {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl IntoResponse for $ty {
+                #[inline]
+                fn into_response(self) -> FibraResult<Response> {
+                    Ok(self.into())
+                }
+            }
+        )*
+    };
+}
+
+impl_into_response! {
+    (Status, &'static str),
+    (Status, String),
+    (Status, &'static [u8]),
+    (Status, Vec<u8>),
+    (),
+    Status,
+    &'static str,
+    String,
+    &'static [u8],
+    Vec<u8>,
+}
+
+impl IntoResponse for Response {
+    #[inline]
+    fn into_response(self) -> FibraResult<Response> {
+        Ok(self)
+    }
+}
+
+impl<T: Into<Body>> IntoResponse for (Status, Mime, T) {
+    #[inline]
+    fn into_response(self) -> FibraResult<Response> {
+        Ok(self.into())
+    }
+}
+
+impl<T: Into<Body>> IntoResponse for (Mime, T) {
+    #[inline]
+    fn into_response(self) -> FibraResult<Response> {
+        Ok(self.into())
+    }
+}
+
+/// Errors are not converted, they are returned and handled by the Catcher
+impl IntoResponse for FibraError {
+    #[inline]
+    fn into_response(self) -> FibraResult<Response> {
+        Err(self)
+    }
+}
+
+impl<T: IntoResponse, E: IntoResponse> IntoResponse for Result<T, E> {
+    #[inline]
+    fn into_response(self) -> FibraResult<Response> {
+        match self {
+            Ok(val) => val.into_response(),
+            Err(err) => err.into_response(),
+        }
+    }
+}
+
+/// None is converted to 404
+impl<T: IntoResponse> IntoResponse for Option<T> {
+    #[inline]
+    fn into_response(self) -> FibraResult<Response> {
+        match self {
+            Some(val) => val.into_response(),
+            None => Ok(Status::NOT_FOUND.into()),
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,270 @@
+//! Server-Sent Events
+use crate::types::*;
+use std::pin::Pin;
+use std::task::Poll;
+use std::time::Duration;
+
+/// One event of a Server-Sent Events stream
+///
+/// # Examples
+///
+/// ```
+/// use fibra::*;
+/// use std::time::Duration;
+///
+/// let event = Event::new().id("42").event("update").data("line1\r\nline2\rline3").retry(Duration::from_secs(3));
+///
+/// assert_eq!(event.to_bytes(), "id: 42\nevent: update\nretry: 3000\ndata: line1\ndata: line2\ndata: line3\n\n");
+/// assert_eq!(Event::new().comment("ping").to_bytes(), ": ping\n\n");
+/// assert_eq!(Event::new().id("a\nb").data("").to_bytes(), "id: ab\ndata: \n\n");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Event {
+    id: Option<String>,
+    event: Option<String>,
+    data: Option<String>,
+    retry: Option<Duration>,
+    comment: Option<String>,
+}
+
+impl Event {
+    /// Create a new object
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the event id, clients send it back as Last-Event-ID when reconnecting, line breaks
+    /// and NULs are removed since they are not allowed
+    #[inline]
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into().replace(['\r', '\n', '\0'], ""));
+        self
+    }
+
+    /// Set the event type, "message" is used by clients if absent, line breaks are removed
+    #[inline]
+    pub fn event(mut self, event: impl Into<String>) -> Self {
+        self.event = Some(event.into().replace(['\r', '\n'], ""));
+        self
+    }
+
+    /// Set the data, multi-line data is split into multiple data fields
+    #[inline]
+    pub fn data(mut self, data: impl Into<String>) -> Self {
+        self.data = Some(data.into());
+        self
+    }
+
+    /// Set the data as json
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    /// use indexmap::indexmap;
+    ///
+    /// assert_eq!(Event::new().json(indexmap!("a" => 1)).to_bytes(), "data: {\"a\":1}\n\n");
+    /// ```
+    #[inline]
+    pub fn json(self, val: impl encoder::json::Encode) -> Self {
+        let mut buf = vec![];
+        val.encode(&mut buf);
+        self.data(String::from_utf8_lossy(&buf))
+    }
+
+    /// Set the reconnection time of the client
+    #[inline]
+    pub fn retry(mut self, retry: Duration) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    /// Set a comment, it's ignored by clients
+    #[inline]
+    pub fn comment(mut self, comment: impl Into<String>) -> Self {
+        self.comment = Some(comment.into());
+        self
+    }
+
+    /// Format the event in the wire format
+    pub fn to_bytes(&self) -> Bytes {
+        let mut buf = String::new();
+
+        if let Some(comment) = &self.comment {
+            lines(comment).for_each(|line| {
+                buf.push_str(": ");
+                buf.push_str(line);
+                buf.push('\n');
+            });
+        }
+
+        if let Some(id) = &self.id {
+            buf.push_str("id: ");
+            buf.push_str(id);
+            buf.push('\n');
+        }
+
+        if let Some(event) = &self.event {
+            buf.push_str("event: ");
+            buf.push_str(event);
+            buf.push('\n');
+        }
+
+        if let Some(retry) = &self.retry {
+            buf.push_str("retry: ");
+            buf.push_str(&retry.as_millis().to_string());
+            buf.push('\n');
+        }
+
+        if let Some(data) = &self.data {
+            lines(data).for_each(|line| {
+                buf.push_str("data: ");
+                buf.push_str(line);
+                buf.push('\n');
+            });
+        }
+
+        buf.push('\n');
+        buf.into()
+    }
+}
+
+/// Server-Sent Events responder, events are streamed with keep-alive comments in between
+///
+/// # Examples
+///
+/// ```
+/// use fibra::*;
+/// use std::time::Duration;
+///
+/// #[tokio::main]
+/// async fn main() -> FibraResult<()> {
+///     let events = futures::stream::iter(vec![Ok(Event::new().id("1").data("Hello")), Ok(Event::new().id("2").data("World"))]);
+///     let mut res = Sse::new(events).into_response()?;
+///
+///     assert_eq!(res.header_ref(header::CONTENT_TYPE).map(|v| v.as_bytes()), Some(mime::TEXT_EVENT_STREAM.as_ref().as_bytes()));
+///     assert_eq!(res.header_ref(header::CACHE_CONTROL).map(|v| v.as_bytes()), Some("no-cache".as_bytes()));
+///     assert_eq!(res.header_ref("x-accel-buffering").map(|v| v.as_bytes()), Some("no".as_bytes()));
+///     assert_eq!(res.body_all().await.unwrap_or_default(), "id: 1\ndata: Hello\n\nid: 2\ndata: World\n\n");
+///
+///     let idle = futures::stream::pending::<FibraResult<Event>>();
+///     let mut res = Sse::new(idle).keep_alive(Duration::from_millis(10)).into_response()?;
+///
+///     assert_eq!(res.body_mut().try_read_frame().await?, Some(":\n\n".into()));
+///     assert_eq!(res.body_mut().try_read_frame().await?, Some(":\n\n".into()));
+///
+///     Ok(())
+/// }
+/// ```
+pub struct Sse<S> {
+    stream: S,
+    keep_alive: Option<Duration>,
+    comment: Bytes,
+}
+
+impl<S> Sse<S> where S: Stream<Item = FibraResult<Event>> + Send + Sync + 'static {
+    /// Create a new object, a keep-alive comment is sent after 15 seconds of idle by default
+    #[inline]
+    pub fn new(stream: S) -> Self {
+        Self { stream, keep_alive: Some(Duration::from_secs(15)), comment: Bytes::from_static(b":\n\n") }
+    }
+
+    /// Set the idle interval of keep-alive comments
+    #[inline]
+    pub fn keep_alive(mut self, interval: Duration) -> Self {
+        self.keep_alive = Some(interval);
+        self
+    }
+
+    /// Set the text of keep-alive comments
+    #[inline]
+    pub fn keep_alive_text(mut self, text: impl Into<String>) -> Self {
+        self.comment = Event::new().comment(text).to_bytes();
+        self
+    }
+
+    /// Disable keep-alive comments
+    #[inline]
+    pub fn no_keep_alive(mut self) -> Self {
+        self.keep_alive = None;
+        self
+    }
+}
+
+impl<S> From<Sse<S>> for Response where S: Stream<Item = FibraResult<Event>> + Send + Sync + 'static {
+    #[inline]
+    fn from(value: Sse<S>) -> Self {
+        let timer = value.keep_alive.map(|interval| (Box::pin(tokio::time::sleep(interval)), interval));
+
+        Response::new()
+            .header(header::CONTENT_TYPE, mime::TEXT_EVENT_STREAM)
+            .header(header::CACHE_CONTROL, "no-cache")
+            .header("x-accel-buffering", "no")
+            .stream(SseStream { stream: Box::pin(value.stream), timer, comment: value.comment })
+    }
+}
+
+impl<S> IntoResponse for Sse<S> where S: Stream<Item = FibraResult<Event>> + Send + Sync + 'static {
+    #[inline]
+    fn into_response(self) -> FibraResult<Response> {
+        Ok(self.into())
+    }
+}
+
+/// The body stream of events and keep-alive comments
+struct SseStream<S> {
+    stream: Pin<Box<S>>,
+    timer: Option<(Pin<Box<tokio::time::Sleep>>, Duration)>,
+    comment: Bytes,
+}
+
+impl<S> Stream for SseStream<S> where S: Stream<Item = FibraResult<Event>> {
+    type Item = FibraResult<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        match this.stream.as_mut().poll_next(cx) {
+            Poll::Ready(Some(Ok(event))) => {
+                if let Some((timer, interval)) = this.timer.as_mut() {
+                    timer.as_mut().reset(tokio::time::Instant::now() + *interval);
+                }
+
+                return Poll::Ready(Some(Ok(event.to_bytes())));
+            }
+            Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
+            Poll::Ready(None) => return Poll::Ready(None),
+            Poll::Pending => {}
+        }
+
+        if let Some((timer, interval)) = this.timer.as_mut() {
+            if timer.as_mut().poll(cx).is_ready() {
+                timer.as_mut().reset(tokio::time::Instant::now() + *interval);
+                return Poll::Ready(Some(Ok(this.comment.clone())));
+            }
+        }
+
+        Poll::Pending
+    }
+}
+
+/// Split the text by CRLF, LF or CR
+fn lines(text: &str) -> impl Iterator<Item = &str> {
+    let mut rest = Some(text);
+
+    std::iter::from_fn(move || {
+        let text = rest?;
+
+        match text.find(['\r', '\n']) {
+            Some(pos) => {
+                rest = Some(&text[pos + if text[pos..].starts_with("\r\n") { 2 } else { 1 }..]);
+                Some(&text[..pos])
+            }
+            None => {
+                rest = None;
+                Some(text)
+            }
+        }
+    })
+}
\ No newline at end of file
@@ -0,0 +1,6 @@
+//! Upgraded Connection
+use crate::types::*;
+
+/// The raw io of an upgraded connection, it's an AsyncRead + AsyncWrite, bytes read ahead by
+/// the server are replayed before the socket is read
+pub type Upgraded = TokioIo<hyper::upgrade::Upgraded>;
\ No newline at end of file
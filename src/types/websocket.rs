@@ -0,0 +1,931 @@
+//! WebSocket
+use crate::types::*;
+use bytes::Buf;
+use bytes::BufMut;
+use std::pin::Pin;
+use std::task::ready;
+use std::task::Poll;
+use futures::Sink;
+use flate2::Compress;
+use flate2::Decompress;
+use flate2::Compression;
+use flate2::FlushCompress;
+use flate2::FlushDecompress;
+use tokio::io::AsyncRead;
+use tokio::io::AsyncWrite;
+use tokio::io::ReadBuf;
+
+/// The GUID to compute Sec-WebSocket-Accept, defined in RFC 6455
+const GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// The tail removed from every compressed message, defined in RFC 7692
+const TAIL: [u8; 4] = [0x00, 0x00, 0xff, 0xff];
+
+/// A complete message, fragmented messages are reassembled before being yielded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    /// UTF-8 text
+    Text(String),
+
+    /// Binary data
+    Binary(Bytes),
+
+    /// Ping, a pong is replied automatically
+    Ping(Bytes),
+
+    /// Pong
+    Pong(Bytes),
+
+    /// Close, the close frame is echoed automatically
+    Close(Option<CloseFrame>),
+}
+
+impl From<String> for Message {
+    #[inline]
+    fn from(value: String) -> Self {
+        Message::Text(value)
+    }
+}
+
+impl From<&str> for Message {
+    #[inline]
+    fn from(value: &str) -> Self {
+        Message::Text(value.to_string())
+    }
+}
+
+impl From<Bytes> for Message {
+    #[inline]
+    fn from(value: Bytes) -> Self {
+        Message::Binary(value)
+    }
+}
+
+impl From<Vec<u8>> for Message {
+    #[inline]
+    fn from(value: Vec<u8>) -> Self {
+        Message::Binary(value.into())
+    }
+}
+
+/// Status code of a close frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CloseCode(pub u16);
+
+impl CloseCode {
+    /// 1000 normal closure
+    pub const NORMAL: CloseCode = CloseCode(1000);
+
+    /// 1001 endpoint is going away
+    pub const GOING_AWAY: CloseCode = CloseCode(1001);
+
+    /// 1002 protocol error
+    pub const PROTOCOL_ERROR: CloseCode = CloseCode(1002);
+
+    /// 1003 unsupported data
+    pub const UNSUPPORTED: CloseCode = CloseCode(1003);
+
+    /// 1007 data is inconsistent with the message type
+    pub const INVALID_DATA: CloseCode = CloseCode(1007);
+
+    /// 1008 policy violation
+    pub const POLICY: CloseCode = CloseCode(1008);
+
+    /// 1009 message is too big
+    pub const TOO_BIG: CloseCode = CloseCode(1009);
+
+    /// 1010 extension is required by the client
+    pub const EXTENSION: CloseCode = CloseCode(1010);
+
+    /// 1011 unexpected condition
+    pub const INTERNAL_ERROR: CloseCode = CloseCode(1011);
+
+    /// Whether the code is allowed to be sent in a close frame
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    ///
+    /// assert_eq!(CloseCode::NORMAL.is_valid(), true);
+    /// assert_eq!(CloseCode(4000).is_valid(), true);
+    /// assert_eq!(CloseCode(1005).is_valid(), false);
+    /// ```
+    #[inline]
+    pub fn is_valid(&self) -> bool {
+        matches!(self.0, 1000..=1003 | 1007..=1014 | 3000..=4999)
+    }
+}
+
+/// The code and reason of a close frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CloseFrame {
+    /// The close code
+    pub code: CloseCode,
+
+    /// The reason, at most 123 bytes
+    pub reason: String,
+}
+
+/// The side of a connection, clients mask their frames and servers don't
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    /// Server side
+    Server,
+
+    /// Client side
+    Client,
+}
+
+/// Options of a connection, deflate fields must match the negotiated extension
+#[derive(Debug, Clone, Copy)]
+pub struct WebSocketConfig {
+    max_message_size: usize,
+    deflate: bool,
+    server_no_context_takeover: bool,
+    client_no_context_takeover: bool,
+}
+
+impl WebSocketConfig {
+    /// Create a new object, 16 MiB per message and no compression by default
+    #[inline]
+    pub fn new() -> Self {
+        Self { max_message_size: 16 * 1024 * 1024, deflate: false, server_no_context_takeover: false, client_no_context_takeover: false }
+    }
+
+    /// Set the max size of a message, applied after decompression
+    #[inline]
+    pub fn max_message_size(mut self, max: usize) -> Self {
+        self.max_message_size = max;
+        self
+    }
+
+    /// Enable permessage-deflate
+    #[inline]
+    pub fn deflate(mut self, enable: bool) -> Self {
+        self.deflate = enable;
+        self
+    }
+
+    /// Reset the server's compression context after each message
+    #[inline]
+    pub fn server_no_context_takeover(mut self, enable: bool) -> Self {
+        self.server_no_context_takeover = enable;
+        self
+    }
+
+    /// Reset the client's compression context after each message
+    #[inline]
+    pub fn client_no_context_takeover(mut self, enable: bool) -> Self {
+        self.client_no_context_takeover = enable;
+        self
+    }
+}
+
+impl Default for WebSocketConfig {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A validated handshake, call `on_upgrade` to reply and take over the connection
+pub struct WebSocketUpgrade {
+    upgrade: hyper::upgrade::OnUpgrade,
+    accept: Option<HeaderValue>,
+    offered: Vec<String>,
+    extension: Option<(bool, bool, bool)>,
+    protocols: Vec<String>,
+    max_message_size: usize,
+    deflate: bool,
+}
+
+impl WebSocketUpgrade {
+    /// Validate the handshake of HTTP/1.1 Upgrade or HTTP/2 extended CONNECT, the latter
+    /// must be enabled by the server and routed with a method other than GET, e.g. `all`
+    pub fn new(req: &mut Request) -> FibraResult<Self> {
+        let invalid = |reason: &str| FibraError::from(Rejection::InvalidUpgrade(reason.to_string()));
+        let headers = req.headers_ref();
+
+        let accept = match *req.version_ref() {
+            Version::HTTP_2 => {
+                match req.method_ref() == Method::CONNECT && req.extensions_ref().get::<hyper::ext::Protocol>().map(|p| p.as_str()) == Some("websocket") {
+                    true => None,
+                    false => return Err(invalid("expect extended connect with websocket protocol")),
+                }
+            }
+            Version::HTTP_11 => {
+                if req.method_ref() != Method::GET {
+                    return Err(invalid("method must be GET"));
+                }
+
+                if !tokens(headers, header::CONNECTION).any(|v| v.eq_ignore_ascii_case("upgrade")) {
+                    return Err(invalid("missing connection upgrade"));
+                }
+
+                if !tokens(headers, header::UPGRADE).any(|v| v.eq_ignore_ascii_case("websocket")) {
+                    return Err(invalid("missing upgrade websocket"));
+                }
+
+                let key = headers.get(header::SEC_WEBSOCKET_KEY).map(|v| v.as_bytes()).unwrap_or_default();
+
+                if base64::Engine::decode(&base64::engine::general_purpose::STANDARD, key).map(|v| v.len()) != Ok(16) {
+                    return Err(invalid("invalid sec-websocket-key"));
+                }
+
+                Some(accept_key(key))
+            }
+            _ => return Err(invalid("expect HTTP/1.1 or HTTP/2")),
+        };
+
+        if headers.get(header::SEC_WEBSOCKET_VERSION).map(|v| v.as_bytes()) != Some(b"13") {
+            return Err(Rejection::UnsupportedWebSocketVersion.into());
+        }
+
+        let offered = tokens(headers, header::SEC_WEBSOCKET_PROTOCOL).map(|v| v.to_string()).collect();
+        let extension = tokens(headers, header::SEC_WEBSOCKET_EXTENSIONS).find_map(negotiate);
+        let upgrade = req.extensions_mut().remove::<hyper::upgrade::OnUpgrade>().ok_or_else(|| invalid("connection is not upgradable"))?;
+
+        Ok(Self { upgrade, accept, offered, extension, protocols: vec![], max_message_size: 16 * 1024 * 1024, deflate: true })
+    }
+
+    /// Set the supported subprotocols, the first one offered by the client is selected
+    #[inline]
+    pub fn protocols<I, S>(mut self, protocols: I) -> Self where I: IntoIterator<Item = S>, S: Into<String> {
+        self.protocols = protocols.into_iter().map(|v| v.into()).collect();
+        self
+    }
+
+    /// Set the max size of a message, 16 MiB by default
+    #[inline]
+    pub fn max_message_size(mut self, max: usize) -> Self {
+        self.max_message_size = max;
+        self
+    }
+
+    /// Accept permessage-deflate when the client offers it, enabled by default
+    #[inline]
+    pub fn deflate(mut self, enable: bool) -> Self {
+        self.deflate = enable;
+        self
+    }
+
+    /// The subprotocol to be selected
+    #[inline]
+    pub fn protocol(&self) -> Option<&str> {
+        self.protocols.iter().find(|v| self.offered.contains(v)).map(|v| v.as_str())
+    }
+
+    /// Reply the handshake and run the callback with the connection once it's upgraded
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fibra::*;
+    /// use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> FibraResult<()> {
+    ///     let mut app = Fibra::new();
+    ///     app.get("/echo", |mut ctx: Context| async move {
+    ///         Ok::<_, FibraError>(ctx.upgrade_websocket()?.on_upgrade(|mut socket| async move {
+    ///             while let Ok(Some(msg)) = socket.recv().await {
+    ///                 if let Message::Text(_) | Message::Binary(_) = msg {
+    ///                     let _ = socket.send(msg).await;
+    ///                 }
+    ///             }
+    ///         }))
+    ///     })?;
+    ///
+    ///     let addr = app.bind("127.0.0.1:0")?.local_addr()?.as_socket().ok_or(FibraError::AddrNotAvailable)?;
+    ///     tokio::spawn(app.run());
+    ///
+    ///     let mut stream = tokio::net::TcpStream::connect(addr).await?;
+    ///     stream.write_all(b"GET /echo HTTP/1.1\r\nHost: localhost\r\nConnection: Upgrade\r\nUpgrade: websocket\r\n\
+    ///                        Sec-WebSocket-Version: 13\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+    ///                        Sec-WebSocket-Extensions: permessage-deflate; client_max_window_bits\r\n\r\n").await?;
+    ///
+    ///     let mut head = vec![];
+    ///     while !head.ends_with(b"\r\n\r\n") {
+    ///         head.push(stream.read_u8().await?);
+    ///     }
+    ///
+    ///     let head = String::from_utf8_lossy(&head).to_lowercase();
+    ///
+    ///     assert_eq!(head.starts_with("http/1.1 101"), true);
+    ///     assert_eq!(head.contains("sec-websocket-accept: s3pplmbitxaq9kygzzhzrbk+xoo="), true);
+    ///     assert_eq!(head.contains("sec-websocket-extensions: permessage-deflate"), true);
+    ///
+    ///     let mut socket = WebSocket::from_raw(stream, Role::Client, WebSocketConfig::new().deflate(true));
+    ///
+    ///     socket.send("Hello World!").await?;
+    ///     socket.send(Message::Ping("beat".into())).await?;
+    ///
+    ///     assert_eq!(socket.recv().await?, Some(Message::Text("Hello World!".into())));
+    ///     assert_eq!(socket.recv().await?, Some(Message::Pong("beat".into())));
+    ///
+    ///     socket.close(CloseCode::NORMAL, "bye").await?;
+    ///
+    ///     assert_eq!(socket.recv().await?, Some(Message::Close(Some(CloseFrame { code: CloseCode::NORMAL, reason: "".into() }))));
+    ///     assert_eq!(socket.recv().await?, None);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn on_upgrade<F, R>(self, f: F) -> Response where F: FnOnce(WebSocket) -> R + Send + 'static, R: Future<Output = ()> + Send + 'static {
+        let protocol = self.protocol().map(|v| v.to_string());
+        let mut config = WebSocketConfig::new().max_message_size(self.max_message_size);
+        let mut res = match self.accept {
+            Some(accept) => Response::new()
+                .status(Status::SWITCHING_PROTOCOLS)
+                .header(header::CONNECTION, "upgrade")
+                .header(header::UPGRADE, "websocket")
+                .header(header::SEC_WEBSOCKET_ACCEPT, accept),
+            None => Response::new().status(Status::OK),
+        };
+
+        if let Some(protocol) = protocol.as_ref().and_then(|v| HeaderValue::try_from(v.as_str()).ok()) {
+            res = res.header(header::SEC_WEBSOCKET_PROTOCOL, protocol);
+        }
+
+        if let Some((server_no_context_takeover, client_no_context_takeover, server_max_window_bits)) = self.extension.filter(|_| self.deflate) {
+            let mut value = "permessage-deflate".to_string();
+
+            if server_no_context_takeover {
+                value.push_str("; server_no_context_takeover");
+            }
+
+            if client_no_context_takeover {
+                value.push_str("; client_no_context_takeover");
+            }
+
+            if server_max_window_bits {
+                value.push_str("; server_max_window_bits=15");
+            }
+
+            if let Ok(value) = HeaderValue::try_from(value) {
+                res = res.header(header::SEC_WEBSOCKET_EXTENSIONS, value);
+                config = config.deflate(true).server_no_context_takeover(server_no_context_takeover).client_no_context_takeover(client_no_context_takeover);
+            }
+        }
+
+        let upgrade = self.upgrade;
+
+        tokio::spawn(async move {
+            if let Ok(io) = upgrade.await {
+                let mut socket = WebSocket::from_raw(TokioIo::new(io), Role::Server, config);
+                socket.protocol = protocol;
+                f(socket).await;
+            }
+        });
+
+        res
+    }
+}
+
+/// The io of a connection
+trait Io: AsyncRead + AsyncWrite + Send + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Unpin> Io for T {}
+
+/// A WebSocket connection, it's a Stream of received messages and a Sink of messages to send,
+/// pings are answered and close frames are echoed when the stream is polled or flushed
+pub struct WebSocket {
+    io: Box<dyn Io>,
+    role: Role,
+    config: WebSocketConfig,
+    protocol: Option<String>,
+    deflate: Option<Deflate>,
+    rbuf: BytesMut,
+    wbuf: BytesMut,
+    partial: Option<(u8, bool, BytesMut)>,
+    sent_close: bool,
+    finished: bool,
+}
+
+impl WebSocket {
+    /// Wrap an io whose handshake is done, the config must match the negotiated options
+    #[inline]
+    pub fn from_raw(io: impl AsyncRead + AsyncWrite + Send + Unpin + 'static, role: Role, config: WebSocketConfig) -> Self {
+        Self {
+            io: Box::new(io),
+            role,
+            config,
+            protocol: None,
+            deflate: config.deflate.then(|| Deflate::new(role, &config)),
+            rbuf: BytesMut::new(),
+            wbuf: BytesMut::new(),
+            partial: None,
+            sent_close: false,
+            finished: false,
+        }
+    }
+
+    /// The selected subprotocol
+    #[inline]
+    pub fn protocol(&self) -> Option<&str> {
+        self.protocol.as_deref()
+    }
+
+    /// Receive the next message, None if the connection is closed
+    #[inline]
+    pub async fn recv(&mut self) -> FibraResult<Option<Message>> {
+        futures::StreamExt::next(self).await.transpose()
+    }
+
+    /// Send a message and flush it
+    #[inline]
+    pub async fn send(&mut self, msg: impl Into<Message>) -> FibraResult<()> {
+        futures::SinkExt::send(self, msg.into()).await
+    }
+
+    /// Start the closing handshake, keep receiving until None to wait for the peer's reply
+    #[inline]
+    pub async fn close(&mut self, code: CloseCode, reason: impl Into<String>) -> FibraResult<()> {
+        self.send(Message::Close(Some(CloseFrame { code, reason: reason.into() }))).await
+    }
+
+    /// Append a frame to the write buffer
+    fn queue(&mut self, opcode: u8, payload: &[u8], compressed: bool) {
+        self.wbuf.reserve(payload.len() + 14);
+        self.wbuf.put_u8(0x80 | if compressed { 0x40 } else { 0 } | opcode);
+
+        let mask = if self.role == Role::Client { 0x80 } else { 0 };
+
+        match payload.len() {
+            len @ 0..=125 => self.wbuf.put_u8(mask | len as u8),
+            len @ 126..=65535 => {
+                self.wbuf.put_u8(mask | 126);
+                self.wbuf.put_u16(len as u16);
+            }
+            len => {
+                self.wbuf.put_u8(mask | 127);
+                self.wbuf.put_u64(len as u64);
+            }
+        }
+
+        if self.role == Role::Server {
+            return self.wbuf.put_slice(payload);
+        }
+
+        let key: [u8; 4] = rand::random();
+        let start = self.wbuf.len();
+
+        self.wbuf.put_slice(&key);
+        self.wbuf.put_slice(payload);
+        self.wbuf[start + 4..].iter_mut().enumerate().for_each(|(i, b)| *b ^= key[i % 4]);
+    }
+
+    /// Encode a message into the write buffer
+    fn encode(&mut self, msg: Message) -> FibraResult<()> {
+        if self.sent_close {
+            return Err(FibraError::WebSocketError("connection is closing".into()));
+        }
+
+        let (opcode, payload) = match msg {
+            Message::Text(text) => return self.encode_data(0x1, text.as_bytes()),
+            Message::Binary(data) => return self.encode_data(0x2, &data),
+            Message::Ping(data) => (0x9, data),
+            Message::Pong(data) => (0xA, data),
+            Message::Close(frame) => {
+                self.sent_close = true;
+                (0x8, close_payload(frame))
+            }
+        };
+
+        if payload.len() > 125 {
+            return Err(FibraError::WebSocketError("control frame is too long".into()));
+        }
+
+        self.queue(opcode, &payload, false);
+
+        Ok(())
+    }
+
+    /// Encode a data message, compressed if deflate is enabled
+    fn encode_data(&mut self, opcode: u8, payload: &[u8]) -> FibraResult<()> {
+        match self.deflate.as_mut() {
+            Some(deflate) => {
+                let compressed = deflate.compress(payload)?;
+                self.queue(opcode, &compressed, true);
+            }
+            None => self.queue(opcode, payload, false),
+        }
+
+        Ok(())
+    }
+
+    /// Parse buffered frames until a message is complete
+    fn decode(&mut self) -> Result<Option<Message>, (CloseCode, &'static str)> {
+        loop {
+            if self.rbuf.len() < 2 {
+                return Ok(None);
+            }
+
+            let (b0, b1) = (self.rbuf[0], self.rbuf[1]);
+            let (fin, rsv1, opcode, masked) = (b0 & 0x80 != 0, b0 & 0x40 != 0, b0 & 0x0f, b1 & 0x80 != 0);
+            let control = opcode & 0x08 != 0;
+
+            if b0 & 0x30 != 0 || !matches!(opcode, 0x0..=0x2 | 0x8..=0xA) {
+                return Err((CloseCode::PROTOCOL_ERROR, "invalid frame header"));
+            }
+
+            if masked != (self.role == Role::Server) {
+                return Err((CloseCode::PROTOCOL_ERROR, "invalid frame masking"));
+            }
+
+            let (mut head, len) = match b1 & 0x7f {
+                126 if self.rbuf.len() >= 4 => (4, u16::from_be_bytes([self.rbuf[2], self.rbuf[3]]) as u64),
+                127 if self.rbuf.len() >= 10 => (10, (&self.rbuf[2..10]).get_u64()),
+                126 | 127 => return Ok(None),
+                len => (2, len as u64),
+            };
+
+            if control && (!fin || rsv1 || len > 125) {
+                return Err((CloseCode::PROTOCOL_ERROR, "invalid control frame"));
+            }
+
+            let buffered = self.partial.as_ref().map(|(_, _, data)| data.len()).unwrap_or(0) as u64;
+
+            if !control && buffered.saturating_add(len) > self.config.max_message_size as u64 {
+                return Err((CloseCode::TOO_BIG, "message is too big"));
+            }
+
+            if masked {
+                head += 4;
+            }
+
+            let total = head + len as usize;
+
+            // the buffer grows as the payload arrives, a declared length pins no memory
+            if self.rbuf.len() < total {
+                return Ok(None);
+            }
+
+            let mut payload = self.rbuf.split_to(total).split_off(head - if masked { 4 } else { 0 });
+
+            if masked {
+                let key = payload.split_to(4);
+                payload.iter_mut().enumerate().for_each(|(i, b)| *b ^= key[i % 4]);
+            }
+
+            match opcode {
+                0x9 => {
+                    if !self.sent_close {
+                        self.queue(0xA, &payload, false);
+                    }
+
+                    return Ok(Some(Message::Ping(payload.freeze())));
+                }
+                0xA => return Ok(Some(Message::Pong(payload.freeze()))),
+                0x8 => {
+                    let frame = match payload.len() {
+                        0 => None,
+                        1 => return Err((CloseCode::PROTOCOL_ERROR, "invalid close frame")),
+                        _ => {
+                            let code = CloseCode(payload.get_u16());
+
+                            if !code.is_valid() {
+                                return Err((CloseCode::PROTOCOL_ERROR, "invalid close code"));
+                            }
+
+                            match String::from_utf8(payload.to_vec()) {
+                                Ok(reason) => Some(CloseFrame { code, reason }),
+                                Err(_) => return Err((CloseCode::INVALID_DATA, "invalid close reason")),
+                            }
+                        }
+                    };
+
+                    if !self.sent_close {
+                        self.sent_close = true;
+                        self.queue(0x8, &close_payload(frame.clone().map(|f| CloseFrame { code: f.code, reason: String::new() })), false);
+                    }
+
+                    self.finished = true;
+
+                    return Ok(Some(Message::Close(frame)));
+                }
+                0x0 => {
+                    match self.partial.as_mut() {
+                        Some((_, _, data)) if !rsv1 => data.extend_from_slice(&payload),
+                        _ => return Err((CloseCode::PROTOCOL_ERROR, "unexpected continuation frame")),
+                    }
+
+                    if let Some((opcode, compressed, data)) = self.partial.take_if(|_| fin) {
+                        return self.message(opcode, compressed, data).map(Some);
+                    }
+                }
+                _ => {
+                    if self.partial.is_some() {
+                        return Err((CloseCode::PROTOCOL_ERROR, "expect continuation frame"));
+                    }
+
+                    if rsv1 && self.deflate.is_none() {
+                        return Err((CloseCode::PROTOCOL_ERROR, "unexpected compressed frame"));
+                    }
+
+                    if fin {
+                        return self.message(opcode, rsv1, payload).map(Some);
+                    }
+
+                    self.partial = Some((opcode, rsv1, payload));
+                }
+            }
+        }
+    }
+
+    /// Build a complete data message
+    fn message(&mut self, opcode: u8, compressed: bool, data: BytesMut) -> Result<Message, (CloseCode, &'static str)> {
+        let data = match (compressed, self.deflate.as_mut()) {
+            (true, Some(deflate)) => deflate.decompress(&data, self.config.max_message_size)?.into(),
+            _ => data.freeze(),
+        };
+
+        match opcode {
+            0x1 => String::from_utf8(data.to_vec()).map(Message::Text).map_err(|_| (CloseCode::INVALID_DATA, "invalid utf-8 text")),
+            _ => Ok(Message::Binary(data)),
+        }
+    }
+
+    /// Write the buffered frames
+    fn poll_write_buf(&mut self, cx: &mut std::task::Context<'_>) -> Poll<FibraResult<()>> {
+        while !self.wbuf.is_empty() {
+            let len = ready!(Pin::new(&mut self.io).poll_write(cx, &self.wbuf))?;
+
+            if len == 0 {
+                return Poll::Ready(Err(std::io::Error::from(std::io::ErrorKind::WriteZero).into()));
+            }
+
+            self.wbuf.advance(len);
+        }
+
+        Poll::Ready(Ok(()))
+    }
+
+    /// Write the buffered frames without waiting, used to deliver automatic replies
+    fn try_write_buf(&mut self, cx: &mut std::task::Context<'_>) -> FibraResult<()> {
+        match self.poll_write_buf(cx) {
+            Poll::Ready(Ok(())) => match Pin::new(&mut self.io).poll_flush(cx) {
+                Poll::Ready(Err(err)) => Err(err.into()),
+                _ => Ok(()),
+            },
+            Poll::Ready(Err(err)) => Err(err),
+            Poll::Pending => Ok(()),
+        }
+    }
+}
+
+impl Stream for WebSocket {
+    type Item = FibraResult<Message>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        if let Err(err) = this.try_write_buf(cx) {
+            this.finished = true;
+            return Poll::Ready(Some(Err(err)));
+        }
+
+        if this.finished {
+            return Poll::Ready(None);
+        }
+
+        loop {
+            match this.decode() {
+                Ok(Some(msg)) => {
+                    this.try_write_buf(cx)?;
+                    return Poll::Ready(Some(Ok(msg)));
+                }
+                Ok(None) => {}
+                Err((code, reason)) => {
+                    if !this.sent_close {
+                        this.sent_close = true;
+                        this.queue(0x8, &close_payload(Some(CloseFrame { code, reason: reason.to_string() })), false);
+                    }
+
+                    this.finished = true;
+                    this.try_write_buf(cx)?;
+
+                    return Poll::Ready(Some(Err(FibraError::WebSocketError(reason.into()))));
+                }
+            }
+
+            let mut chunk = [0u8; 8192];
+            let mut buf = ReadBuf::new(&mut chunk);
+
+            if let Err(err) = ready!(Pin::new(&mut this.io).poll_read(cx, &mut buf)) {
+                this.finished = true;
+                return Poll::Ready(Some(Err(err.into())));
+            }
+
+            if buf.filled().is_empty() {
+                this.finished = true;
+                return Poll::Ready(None);
+            }
+
+            this.rbuf.extend_from_slice(buf.filled());
+        }
+    }
+}
+
+impl Sink<Message> for WebSocket {
+    type Error = FibraError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<FibraResult<()>> {
+        let this = self.get_mut();
+
+        // apply backpressure once enough frames are buffered
+        if this.wbuf.len() >= 64 * 1024 {
+            ready!(this.poll_write_buf(cx))?;
+        }
+
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Message) -> FibraResult<()> {
+        self.get_mut().encode(item)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<FibraResult<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_write_buf(cx))?;
+        Pin::new(&mut this.io).poll_flush(cx).map_err(Into::into)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<FibraResult<()>> {
+        let this = self.get_mut();
+
+        if !this.sent_close {
+            this.sent_close = true;
+            this.queue(0x8, &close_payload(Some(CloseFrame { code: CloseCode::NORMAL, reason: String::new() })), false);
+        }
+
+        ready!(this.poll_write_buf(cx))?;
+        ready!(Pin::new(&mut this.io).poll_flush(cx))?;
+        Pin::new(&mut this.io).poll_shutdown(cx).map_err(Into::into)
+    }
+}
+
+/// The permessage-deflate contexts
+struct Deflate {
+    compress: Compress,
+    decompress: Decompress,
+    reset_compress: bool,
+    reset_decompress: bool,
+}
+
+impl Deflate {
+    /// Create the contexts of one side
+    fn new(role: Role, config: &WebSocketConfig) -> Self {
+        let (reset_compress, reset_decompress) = match role {
+            Role::Server => (config.server_no_context_takeover, config.client_no_context_takeover),
+            Role::Client => (config.client_no_context_takeover, config.server_no_context_takeover),
+        };
+
+        Self { compress: Compress::new(Compression::fast(), false), decompress: Decompress::new(false), reset_compress, reset_decompress }
+    }
+
+    /// Compress a message and remove the tail
+    fn compress(&mut self, data: &[u8]) -> FibraResult<Vec<u8>> {
+        let start = self.compress.total_in();
+        let mut out = Vec::with_capacity(data.len() / 2 + 64);
+
+        loop {
+            let consumed = (self.compress.total_in() - start) as usize;
+
+            if out.capacity() - out.len() < 64 {
+                out.reserve(out.capacity().max(64));
+            }
+
+            self.compress.compress_vec(&data[consumed..], &mut out, FlushCompress::Sync).map_err(|err| FibraError::WebSocketError(err.to_string().into()))?;
+
+            if (self.compress.total_in() - start) as usize == data.len() && out.len() < out.capacity() {
+                break;
+            }
+        }
+
+        if out.ends_with(&TAIL) {
+            out.truncate(out.len() - TAIL.len());
+        }
+
+        if self.reset_compress {
+            self.compress.reset();
+        }
+
+        Ok(out)
+    }
+
+    /// Append the tail and decompress a message, the output is limited to the max size
+    fn decompress(&mut self, data: &[u8], max: usize) -> Result<Vec<u8>, (CloseCode, &'static str)> {
+        let input = [data, &TAIL].concat();
+        let start = self.decompress.total_in();
+        let mut out = Vec::with_capacity((data.len() * 2).clamp(64, max.saturating_add(1)));
+
+        loop {
+            let consumed = (self.decompress.total_in() - start) as usize;
+            let written = out.len();
+
+            if out.len() == out.capacity() {
+                out.reserve(out.capacity().min(max.saturating_add(1) - out.len()).max(64));
+            }
+
+            self.decompress.decompress_vec(&input[consumed..], &mut out, FlushDecompress::Sync).map_err(|_| (CloseCode::INVALID_DATA, "invalid compressed data"))?;
+
+            if out.len() > max {
+                return Err((CloseCode::TOO_BIG, "message is too big"));
+            }
+
+            let drained = (self.decompress.total_in() - start) as usize == input.len();
+
+            if drained && out.len() < out.capacity() {
+                break;
+            }
+
+            if !drained && out.len() == written && (self.decompress.total_in() - start) as usize == consumed {
+                return Err((CloseCode::INVALID_DATA, "invalid compressed data"));
+            }
+        }
+
+        if self.reset_decompress {
+            self.decompress.reset(false);
+        }
+
+        Ok(out)
+    }
+}
+
+/// Sec-WebSocket-Accept of a key
+fn accept_key(key: &[u8]) -> HeaderValue {
+    use sha1::Digest;
+
+    let hash = sha1::Sha1::new().chain_update(key).chain_update(GUID).finalize();
+    let accept = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, hash);
+
+    HeaderValue::try_from(accept).unwrap_or_else(|_| unreachable!())
+}
+
+/// The payload of a close frame, long reasons are truncated
+fn close_payload(frame: Option<CloseFrame>) -> Bytes {
+    let frame = match frame {
+        Some(frame) => frame,
+        None => return Bytes::new(),
+    };
+
+    let mut reason = frame.reason.as_str();
+
+    while reason.len() > 123 {
+        let mut end = 123;
+
+        while !reason.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        reason = &reason[..end];
+    }
+
+    let mut payload = BytesMut::with_capacity(2 + reason.len());
+    payload.put_u16(frame.code.0);
+    payload.put_slice(reason.as_bytes());
+    payload.freeze()
+}
+
+/// The comma separated tokens of a header
+fn tokens(headers: &HeaderMap, key: HeaderName) -> impl Iterator<Item = &str> {
+    headers.get_all(key).into_iter().filter_map(|v| v.to_str().ok()).flat_map(|v| v.split(',')).map(|v| v.trim()).filter(|v| !v.is_empty())
+}
+
+/// Negotiate one permessage-deflate offer, offers with params we can't honor are declined
+fn negotiate(offer: &str) -> Option<(bool, bool, bool)> {
+    let mut params = offer.split(';').map(|v| v.trim());
+
+    if params.next() != Some("permessage-deflate") {
+        return None;
+    }
+
+    let mut seen = vec![];
+    let (mut server_no_context_takeover, mut client_no_context_takeover, mut server_max_window_bits) = (false, false, false);
+
+    for param in params {
+        let (key, val) = match param.split_once('=') {
+            Some((key, val)) => (key.trim(), Some(val.trim().trim_matches('"'))),
+            None => (param, None),
+        };
+
+        if seen.contains(&key) {
+            return None;
+        }
+
+        seen.push(key);
+
+        match (key, val) {
+            ("server_no_context_takeover", None) => server_no_context_takeover = true,
+            ("client_no_context_takeover", None) => client_no_context_takeover = true,
+            ("server_max_window_bits", Some("15")) => server_max_window_bits = true,
+            ("client_max_window_bits", None) => {}
+            ("client_max_window_bits", Some(bits)) if matches!(bits.parse::<u8>(), Ok(8..=15)) => {}
+            _ => return None,
+        }
+    }
+
+    Some((server_no_context_takeover, client_no_context_takeover, server_max_window_bits))
+}
\ No newline at end of file